@@ -0,0 +1,59 @@
+use std::env::current_dir;
+use tempfile::NamedTempFile;
+
+use redb::{CachePolicy, Database, ReadableDatabase, ReadableTable, TableDefinition};
+use std::time::Instant;
+
+// Measures how well each of redb's cache policies keeps a working set cached, while the whole
+// database is repeatedly scanned
+fn main() {
+    const TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+    const ITERATIONS: usize = 3;
+    const VALUE_SIZE: usize = 4096;
+    const ELEMENTS: u64 = 100_000;
+    const WORKING_SET: u64 = 10_000;
+    const CACHE_SIZE: usize = 4 * WORKING_SET as usize * VALUE_SIZE;
+
+    for policy in [
+        CachePolicy::Lru,
+        CachePolicy::TwoQueue,
+        CachePolicy::Adaptive,
+    ] {
+        let tmpfile = NamedTempFile::new_in(current_dir().unwrap()).unwrap();
+        let db = Database::builder()
+            .set_cache_size(CACHE_SIZE)
+            .set_cache_policy(policy)
+            .create(tmpfile.path())
+            .unwrap();
+        let value = vec![0u8; VALUE_SIZE];
+        let txn = db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(TABLE).unwrap();
+            for i in 0..ELEMENTS {
+                table.insert(i, value.as_slice()).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+
+        let txn = db.begin_read().unwrap();
+        let table = txn.open_table(TABLE).unwrap();
+        for _ in 0..2 {
+            for i in 0..WORKING_SET {
+                table.get(i).unwrap().unwrap();
+            }
+        }
+        for _ in 0..ITERATIONS {
+            for entry in table.iter().unwrap() {
+                entry.unwrap();
+            }
+            let start = Instant::now();
+            for i in 0..WORKING_SET {
+                table.get(i).unwrap().unwrap();
+            }
+            println!(
+                "redb {policy:?}: Read {WORKING_SET} item working set after a full scan in {}us",
+                start.elapsed().as_micros()
+            );
+        }
+    }
+}
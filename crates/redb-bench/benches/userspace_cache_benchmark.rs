@@ -1,4 +1,3 @@
-#[cfg(target_os = "linux")]
 use std::env::current_dir;
 #[cfg(target_os = "linux")]
 use tempfile::NamedTempFile;
@@ -0,0 +1,163 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Data, DeriveInput, ExprPath, Fields, LitInt, LitStr, Member, Token, Type};
+
+// Attributes of the struct or enum, from `#[redb(...)]`
+pub(crate) struct ContainerAttributes {
+    // `type_name = "..."`, which replaces the generated type name
+    pub(crate) type_name: Option<LitStr>,
+    // `order = [...]`, which lists the fields that keys are compared by, in order
+    pub(crate) order: Option<Vec<Member>>,
+    // `version = N`, which prefixes the encoding with a version tag
+    pub(crate) version: Option<u16>,
+    // `previous = "..."`, the versioned type which reads older versions
+    pub(crate) previous: Option<Type>,
+    // `upgrade = "..."`, the function which converts the previous type. Defaults to `From::from`
+    pub(crate) upgrade: Option<ExprPath>,
+}
+
+impl ContainerAttributes {
+    pub(crate) fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut type_name = None;
+        let mut order = None;
+        let mut version = None;
+        let mut previous = None;
+        let mut upgrade = None;
+        for attr in redb_attributes(&input.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("type_name") {
+                    type_name = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("order") {
+                    if !matches!(input.data, Data::Struct(_)) {
+                        return Err(meta.error("`order` is only supported for structs"));
+                    }
+                    let value = meta.value()?;
+                    let content;
+                    syn::bracketed!(content in value);
+                    let members = Punctuated::<Member, Token![,]>::parse_terminated(&content)?;
+                    order = Some(members.into_iter().collect());
+                    Ok(())
+                } else if meta.path.is_ident("version") {
+                    version = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("previous") {
+                    previous = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("upgrade") {
+                    upgrade = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported redb attribute"))
+                }
+            })?;
+        }
+
+        if let Some(version) = &version {
+            // Tables written by older versions are only opened if they share the type name
+            if type_name.is_none() {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    format!(
+                        "`version = {version}` requires `type_name`, which must be the same for all versions"
+                    ),
+                ));
+            }
+        } else if previous.is_some() || upgrade.is_some() {
+            return Err(syn::Error::new_spanned(
+                input,
+                "`previous` and `upgrade` can only be used with `version`",
+            ));
+        }
+        if previous.is_none() && upgrade.is_some() {
+            return Err(syn::Error::new_spanned(
+                input,
+                "`upgrade` can only be used with `previous`",
+            ));
+        }
+
+        Ok(Self {
+            type_name,
+            order,
+            version,
+            previous,
+            upgrade,
+        })
+    }
+}
+
+// A field of a struct or enum variant, along with its `#[redb(...)]` attributes
+pub(crate) struct FieldInfo {
+    // Name, or index, of the field
+    pub(crate) member: Member,
+    // Type which encodes the field: the type given by `with = "..."`, or else the field's own type
+    pub(crate) ty: Type,
+    // Expression which initializes the field, if it is not stored because of `skip`
+    pub(crate) skip: Option<TokenStream>,
+}
+
+impl FieldInfo {
+    pub(crate) fn parse_all(fields: &Fields) -> syn::Result<Vec<Self>> {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let member = match &field.ident {
+                    Some(ident) => Member::Named(ident.clone()),
+                    None => Member::Unnamed(index.into()),
+                };
+                let mut skip = false;
+                let mut default: Option<ExprPath> = None;
+                let mut with: Option<Type> = None;
+                for attr in redb_attributes(&field.attrs) {
+                    attr.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("skip") {
+                            skip = true;
+                            Ok(())
+                        } else if meta.path.is_ident("default") {
+                            default = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                            Ok(())
+                        } else if meta.path.is_ident("with") {
+                            with = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                            Ok(())
+                        } else {
+                            Err(meta.error("unsupported redb attribute"))
+                        }
+                    })?;
+                }
+
+                if default.is_some() && !skip {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "`default` can only be used with `skip`",
+                    ));
+                }
+                if with.is_some() && skip {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "`with` cannot be used with `skip`",
+                    ));
+                }
+
+                let skip = skip.then(|| {
+                    if let Some(path) = default {
+                        quote! { #path() }
+                    } else {
+                        quote! { Default::default() }
+                    }
+                });
+
+                Ok(Self {
+                    member,
+                    ty: with.unwrap_or_else(|| field.ty.clone()),
+                    skip,
+                })
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn redb_attributes(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("redb"))
+}
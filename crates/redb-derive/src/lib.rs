@@ -6,11 +6,17 @@
     clippy::too_many_lines
 )]
 
+mod attributes;
+
+use crate::attributes::{ContainerAttributes, FieldInfo, redb_attributes};
 use proc_macro::TokenStream;
+use proc_macro2::Literal;
 use quote::quote;
-use syn::{Data, DeriveInput, Fields, GenericParam, Ident, parse_macro_input};
+use syn::{
+    Data, DataEnum, DeriveInput, Fields, GenericParam, Ident, Member, Variant, parse_macro_input,
+};
 
-#[proc_macro_derive(Key)]
+#[proc_macro_derive(Key, attributes(redb))]
 pub fn derive_key(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
@@ -21,29 +27,102 @@ pub fn derive_key(input: TokenStream) -> TokenStream {
 }
 
 fn generate_key_impl(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
-    let Data::Struct(_) = &input.data else {
-        return Err(syn::Error::new_spanned(
-            input,
-            "Key can only be derived for structs",
-        ));
-    };
-
     let name = &input.ident;
     let generics = &input.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
+    let attributes = ContainerAttributes::parse(input)?;
+
+    let compare_impl = match &input.data {
+        Data::Struct(data_struct) => {
+            if let Some(order) = &attributes.order {
+                let fields = FieldInfo::parse_all(&data_struct.fields)?;
+                generate_ordered_compare(&fields, order)?
+            } else {
+                quote! {
+                    let value1 = #name::from_bytes(data1);
+                    let value2 = #name::from_bytes(data2);
+                    Ord::cmp(&value1, &value2)
+                }
+            }
+        }
+        // The discriminant of versioned enums follows the version tag, which may be that of an
+        // older version
+        Data::Enum(_) if attributes.version.is_some() => quote! {
+            let value1 = <Self as redb::Value>::from_bytes(data1);
+            let value2 = <Self as redb::Value>::from_bytes(data2);
+            Ord::cmp(&value1, &value2)
+        },
+        Data::Enum(data_enum) => {
+            // Variants are ordered by their discriminant, which is their index in declaration
+            // order, so values are only deserialized when they are of the same variant
+            let width = discriminant_width(input, data_enum)?;
+            let discriminant1 = generate_read_discriminant(width, &quote! { data1 });
+            let discriminant2 = generate_read_discriminant(width, &quote! { data2 });
+            quote! {
+                Ord::cmp(&#discriminant1, &#discriminant2).then_with(|| {
+                    let value1 = <Self as redb::Value>::from_bytes(data1);
+                    let value2 = <Self as redb::Value>::from_bytes(data2);
+                    Ord::cmp(&value1, &value2)
+                })
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "Key can only be derived for structs and enums",
+            ));
+        }
+    };
+
     Ok(quote! {
         impl #impl_generics redb::Key for #name #ty_generics #where_clause {
             fn compare(data1: &[u8], data2: &[u8]) -> std::cmp::Ordering {
-                let value1 = #name::from_bytes(data1);
-                let value2 = #name::from_bytes(data2);
-                Ord::cmp(&value1, &value2)
+                #compare_impl
             }
         }
     })
 }
 
-#[proc_macro_derive(Value)]
+// Compares the fields listed in `order`, and then the remaining stored fields in declaration order
+fn generate_ordered_compare(
+    fields: &[FieldInfo],
+    order: &[Member],
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut members: Vec<&Member> = vec![];
+    for member in order {
+        let Some(field) = fields.iter().find(|field| field.member == *member) else {
+            return Err(syn::Error::new_spanned(member, "unknown field in `order`"));
+        };
+        if field.skip.is_some() {
+            return Err(syn::Error::new_spanned(
+                member,
+                "skipped fields cannot be used in `order`",
+            ));
+        }
+        if members.contains(&member) {
+            return Err(syn::Error::new_spanned(
+                member,
+                "duplicate field in `order`",
+            ));
+        }
+        members.push(member);
+    }
+    for field in stored_fields(fields) {
+        if !members.contains(&&field.member) {
+            members.push(&field.member);
+        }
+    }
+
+    Ok(quote! {
+        let value1 = <Self as redb::Value>::from_bytes(data1);
+        let value2 = <Self as redb::Value>::from_bytes(data2);
+        std::cmp::Ordering::Equal
+            #(.then_with(|| Ord::cmp(&value1.#members, &value2.#members)))*
+    })
+}
+
+#[proc_macro_derive(Value, attributes(redb))]
 pub fn derive_value(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
@@ -54,23 +133,61 @@ pub fn derive_value(input: TokenStream) -> TokenStream {
 }
 
 fn generate_value_impl(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
-    let Data::Struct(data_struct) = &input.data else {
-        return Err(syn::Error::new_spanned(
-            input,
-            "Value can only be derived for structs",
-        ));
-    };
-
     let name = &input.ident;
     let generics = &input.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let self_type = generate_self_type(name, generics)?;
+    let attributes = ContainerAttributes::parse(input)?;
 
-    let type_name_impl = generate_type_name(name, &data_struct.fields);
-    let as_bytes_impl = generate_as_bytes(&data_struct.fields);
-    let from_bytes_impl = generate_from_bytes(name, &data_struct.fields);
-    let fixed_width_impl = generate_fixed_width(&data_struct.fields);
+    let (type_name, as_bytes_impl, from_bytes_impl, fixed_width_impl) = match &input.data {
+        Data::Struct(data_struct) => {
+            let fields = FieldInfo::parse_all(&data_struct.fields)?;
+            let field_refs: Vec<_> = stored_fields(&fields)
+                .map(|field| {
+                    let member = &field.member;
+                    quote! { &value.#member }
+                })
+                .collect();
+            (
+                generate_type_name(name, &data_struct.fields, &fields),
+                generate_as_bytes(&stored_field_types(&fields), &field_refs),
+                generate_from_bytes(&quote! { #name }, &data_struct.fields, &fields),
+                generate_fixed_width(&fields),
+            )
+        }
+        Data::Enum(data_enum) => {
+            let width = discriminant_width(input, data_enum)?;
+            let variants = parse_variants(data_enum)?;
+            (
+                generate_enum_type_name(name, &variants),
+                generate_enum_as_bytes(name, &variants, width),
+                generate_enum_from_bytes(name, &variants, width),
+                generate_enum_fixed_width(&variants, width),
+            )
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "Value can only be derived for structs and enums",
+            ));
+        }
+    };
+    let (as_bytes_impl, from_bytes_impl, fixed_width_impl) =
+        if let Some(version) = attributes.version {
+            let (as_bytes_impl, from_bytes_impl) =
+                generate_versioned(name, version, &attributes, &as_bytes_impl, &from_bytes_impl);
+            // The width must be the same for all versions, as the type name is, so that tables
+            // written by older versions can be opened
+            (as_bytes_impl, from_bytes_impl, quote! { None })
+        } else {
+            (as_bytes_impl, from_bytes_impl, fixed_width_impl)
+        };
+    let type_name_impl = if let Some(type_name) = &attributes.type_name {
+        quote! { redb::TypeName::new(#type_name) }
+    } else {
+        quote! { redb::TypeName::new(&#type_name) }
+    };
 
     Ok(quote! {
         impl #impl_generics redb::Value for #name #ty_generics #where_clause {
@@ -106,6 +223,57 @@ fn generate_value_impl(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStr
     })
 }
 
+// Versioned values are prefixed with their version, as a little-endian u16. Values of other
+// versions are read by the previous type, and then upgraded. Every value must have the prefix:
+// values stored before versioning was added cannot be told apart from versioned ones, so they
+// have to be rewritten, rather than read by a previous type
+fn generate_versioned(
+    name: &Ident,
+    version: u16,
+    attributes: &ContainerAttributes,
+    as_bytes_impl: &proc_macro2::TokenStream,
+    from_bytes_impl: &proc_macro2::TokenStream,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let as_bytes = quote! {
+        {
+            let mut result = #version.to_le_bytes().to_vec();
+            result.extend_from_slice(&#as_bytes_impl);
+            result
+        }
+    };
+
+    let other_version = if let Some(previous) = &attributes.previous {
+        let upgrade = if let Some(upgrade) = &attributes.upgrade {
+            quote! { #upgrade }
+        } else {
+            quote! { From::from }
+        };
+        quote! { #upgrade(<#previous as redb::Value>::from_bytes(data)) }
+    } else {
+        quote! { panic!("unknown version {} of {}", version, stringify!(#name)) }
+    };
+    let from_bytes = quote! {
+        {
+            let Some(tag) = data.get(..2) else {
+                panic!(
+                    "corrupt value of {}: {} bytes is too short for a version tag",
+                    stringify!(#name),
+                    data.len()
+                );
+            };
+            let version = u16::from_le_bytes(tag.try_into().unwrap());
+            if version == #version {
+                let data = &data[2..];
+                #from_bytes_impl
+            } else {
+                #other_version
+            }
+        }
+    };
+
+    (as_bytes, from_bytes)
+}
+
 fn generate_self_type(
     name: &syn::Ident,
     generics: &syn::Generics,
@@ -120,13 +288,13 @@ fn generate_self_type(
                 GenericParam::Type(type_param) => {
                     return Err(syn::Error::new_spanned(
                         type_param,
-                        "Value derivation is not implemented for structs with type parameters",
+                        "Value derivation is not implemented for types with type parameters",
                     ));
                 }
                 GenericParam::Const(const_param) => {
                     return Err(syn::Error::new_spanned(
                         const_param,
-                        "Value derivation is not implemented for structs with const parameters",
+                        "Value derivation is not implemented for types with const parameters",
                     ));
                 }
             }
@@ -136,14 +304,17 @@ fn generate_self_type(
     }
 }
 
-fn generate_type_name(struct_name: &Ident, fields: &Fields) -> proc_macro2::TokenStream {
+// Returns an expression which evaluates to the name of a struct, or of an enum variant
+fn generate_type_name(
+    name: &Ident,
+    fields: &Fields,
+    infos: &[FieldInfo],
+) -> proc_macro2::TokenStream {
     match fields {
-        Fields::Named(fields_named) => {
-            let field_strings: Vec<_> = fields_named
-                .named
-                .iter()
+        Fields::Named(_) => {
+            let field_strings: Vec<_> = stored_fields(infos)
                 .map(|field| {
-                    let field_name = field.ident.as_ref().unwrap();
+                    let field_name = &field.member;
                     let field_type = &field.ty;
                     quote! {
                         format!("{}: {}", stringify!(#field_name), <#field_type>::type_name().name())
@@ -153,23 +324,19 @@ fn generate_type_name(struct_name: &Ident, fields: &Fields) -> proc_macro2::Toke
 
             if field_strings.is_empty() {
                 quote! {
-                    redb::TypeName::new(&format!("{} {{}}",
-                        stringify!(#struct_name),
-                    ))
+                    format!("{} {{}}", stringify!(#name))
                 }
             } else {
                 quote! {
-                    redb::TypeName::new(&format!("{} {{{}}}",
-                        stringify!(#struct_name),
+                    format!("{} {{{}}}",
+                        stringify!(#name),
                         [#(#field_strings),*].join(", ")
-                    ))
+                    )
                 }
             }
         }
-        Fields::Unnamed(fields_unnamed) => {
-            let field_strings: Vec<_> = fields_unnamed
-                .unnamed
-                .iter()
+        Fields::Unnamed(_) => {
+            let field_strings: Vec<_> = stored_fields(infos)
                 .map(|field| {
                     let field_type = &field.ty;
                     quote! {
@@ -180,47 +347,42 @@ fn generate_type_name(struct_name: &Ident, fields: &Fields) -> proc_macro2::Toke
 
             if field_strings.is_empty() {
                 quote! {
-                    redb::TypeName::new(&format!("{}()",
-                        stringify!(#struct_name),
-                    ))
+                    format!("{}()", stringify!(#name))
                 }
             } else {
                 quote! {
-                    redb::TypeName::new(&format!("{}({})",
-                        stringify!(#struct_name),
+                    format!("{}({})",
+                        stringify!(#name),
                         [#(#field_strings),*].join(", ")
-                    ))
+                    )
                 }
             }
         }
         Fields::Unit => {
             quote! {
-                redb::TypeName::new(stringify!(#struct_name))
+                stringify!(#name).to_string()
             }
         }
     }
 }
 
-fn get_field_types(fields: &Fields) -> Vec<syn::Type> {
-    match fields {
-        Fields::Named(fields_named) => fields_named
-            .named
-            .iter()
-            .map(|field| &field.ty)
-            .cloned()
-            .collect(),
-        Fields::Unnamed(fields_unnamed) => fields_unnamed
-            .unnamed
-            .iter()
-            .map(|field| &field.ty)
-            .cloned()
-            .collect(),
-        Fields::Unit => vec![],
-    }
+// Fields which are not skipped, and so are encoded
+fn stored_fields(fields: &[FieldInfo]) -> impl Iterator<Item = &FieldInfo> {
+    fields.iter().filter(|field| field.skip.is_none())
 }
 
-fn generate_fixed_width(fields: &Fields) -> proc_macro2::TokenStream {
-    let field_types = get_field_types(fields);
+fn stored_field_types(fields: &[FieldInfo]) -> Vec<syn::Type> {
+    stored_fields(fields)
+        .map(|field| field.ty.clone())
+        .collect()
+}
+
+fn field_var(index: usize) -> Ident {
+    quote::format_ident!("field_{}", index)
+}
+
+fn generate_fixed_width(fields: &[FieldInfo]) -> proc_macro2::TokenStream {
+    let field_types = stored_field_types(fields);
     quote! {
         let mut total_width = 0usize;
         #(
@@ -230,42 +392,28 @@ fn generate_fixed_width(fields: &Fields) -> proc_macro2::TokenStream {
     }
 }
 
-fn generate_as_bytes(fields: &Fields) -> proc_macro2::TokenStream {
-    let field_types = get_field_types(fields);
-    let field_accessors = match fields {
-        Fields::Named(fields_named) => fields_named
-            .named
-            .iter()
-            .map(|field| {
-                let name = &field.ident;
-                quote! { #name }
-            })
-            .collect(),
-        Fields::Unnamed(_) => (0..field_types.len())
-            .map(|i| {
-                let index = syn::Index::from(i);
-                quote! { #index }
-            })
-            .collect(),
-        Fields::Unit => Vec::new(),
-    };
-
+// `field_refs` are expressions which evaluate to a reference to each field
+fn generate_as_bytes(
+    field_types: &[syn::Type],
+    field_refs: &[proc_macro2::TokenStream],
+) -> proc_macro2::TokenStream {
     let num_fields = field_types.len();
 
     if num_fields == 0 {
         quote! { Vec::new() }
     } else if num_fields == 1 {
-        let field_accessor = &field_accessors[0];
+        let field_ref = &field_refs[0];
         let field_type = &field_types[0];
         quote! {
             {
-                let field_bytes = <#field_type>::as_bytes(&value.#field_accessor);
-                field_bytes.as_ref().to_vec()
+                let field_bytes = <#field_type>::as_bytes(#field_ref);
+                let bytes: &[u8] = field_bytes.as_ref();
+                bytes.to_vec()
             }
         }
     } else {
         let field_types_except_last = &field_types[..num_fields - 1];
-        let field_accessors_except_last = &field_accessors[..num_fields - 1];
+        let field_refs_except_last = &field_refs[..num_fields - 1];
 
         quote! {
             {
@@ -273,7 +421,7 @@ fn generate_as_bytes(fields: &Fields) -> proc_macro2::TokenStream {
 
                 #(
                     if <#field_types_except_last>::fixed_width().is_none() {
-                        let field_bytes = <#field_types_except_last>::as_bytes(&value.#field_accessors_except_last);
+                        let field_bytes = <#field_types_except_last>::as_bytes(#field_refs_except_last);
                         let bytes: &[u8] = field_bytes.as_ref();
                         let len = bytes.len();
                         if len < 254 {
@@ -291,7 +439,7 @@ fn generate_as_bytes(fields: &Fields) -> proc_macro2::TokenStream {
 
                 #(
                     {
-                        let field_bytes = <#field_types>::as_bytes(&value.#field_accessors);
+                        let field_bytes = <#field_types>::as_bytes(#field_refs);
                         result.extend_from_slice(field_bytes.as_ref());
                     }
                 )*
@@ -302,10 +450,19 @@ fn generate_as_bytes(fields: &Fields) -> proc_macro2::TokenStream {
     }
 }
 
-fn generate_from_bytes(name: &Ident, fields: &Fields) -> proc_macro2::TokenStream {
-    let field_types = get_field_types(fields);
-    let field_vars: Vec<_> = (0..field_types.len())
-        .map(|i| quote::format_ident!("field_{}", i))
+// `constructor` is the path of the struct, or enum variant, to construct. Each stored field is
+// decoded into `field_{index}`
+fn generate_from_bytes(
+    constructor: &proc_macro2::TokenStream,
+    fields: &Fields,
+    infos: &[FieldInfo],
+) -> proc_macro2::TokenStream {
+    let field_types = stored_field_types(infos);
+    let field_vars: Vec<_> = infos
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| field.skip.is_none())
+        .map(|(i, _)| field_var(i))
         .collect();
     let num_fields = field_types.len();
 
@@ -314,8 +471,13 @@ fn generate_from_bytes(name: &Ident, fields: &Fields) -> proc_macro2::TokenStrea
     } else if num_fields == 1 {
         let field_var = &field_vars[0];
         let field_type = &field_types[0];
+        // Fixed width enum variants may be followed by padding
         quote! {
-            let #field_var = <#field_type>::from_bytes(data);
+            let #field_var = if let Some(fixed_width) = <#field_type>::fixed_width() {
+                <#field_type>::from_bytes(&data[..fixed_width])
+            } else {
+                <#field_type>::from_bytes(data)
+            };
         }
     } else {
         let field_types_except_last = &field_types[..num_fields - 1];
@@ -368,19 +530,27 @@ fn generate_from_bytes(name: &Ident, fields: &Fields) -> proc_macro2::TokenStrea
             };
         }
     };
+    let field_values: Vec<_> = infos
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            if let Some(initializer) = &field.skip {
+                initializer.clone()
+            } else {
+                let var = field_var(i);
+                quote! { #var }
+            }
+        })
+        .collect();
     match fields {
-        Fields::Named(fields_named) => {
-            let field_names: Vec<_> = fields_named
-                .named
-                .iter()
-                .map(|field| &field.ident)
-                .collect();
+        Fields::Named(_) => {
+            let field_names: Vec<_> = infos.iter().map(|field| &field.member).collect();
 
             quote! {
                 {
                     #body
-                    #name {
-                        #(#field_names: #field_vars),*
+                    #constructor {
+                        #(#field_names: #field_values),*
                     }
                 }
             }
@@ -389,12 +559,244 @@ fn generate_from_bytes(name: &Ident, fields: &Fields) -> proc_macro2::TokenStrea
             quote! {
                 {
                     #body
-                    #name(#(#field_vars),*)
+                    #constructor(#(#field_values),*)
                 }
             }
         }
         Fields::Unit => {
-            quote! { #name }
+            quote! { #constructor }
+        }
+    }
+}
+
+// Each enum value is encoded as the index of its variant, in declaration order, followed by the
+// fields of the variant encoded in the same way as those of a struct. The index is one byte, or
+// two bytes for enums with more than 256 variants
+fn discriminant_width(input: &DeriveInput, data_enum: &DataEnum) -> syn::Result<usize> {
+    match data_enum.variants.len() {
+        0 => Err(syn::Error::new_spanned(
+            input,
+            "Key and Value cannot be derived for enums with no variants",
+        )),
+        1..=256 => Ok(1),
+        257..=65536 => Ok(2),
+        _ => Err(syn::Error::new_spanned(
+            input,
+            "Key and Value cannot be derived for enums with more than 65536 variants",
+        )),
+    }
+}
+
+fn generate_read_discriminant(
+    width: usize,
+    data: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    if width == 1 {
+        quote! { #data[0] }
+    } else {
+        quote! { u16::from_le_bytes(#data[..2].try_into().unwrap()) }
+    }
+}
+
+fn discriminant_literal(width: usize, index: usize) -> Literal {
+    if width == 1 {
+        Literal::u8_suffixed(index.try_into().unwrap())
+    } else {
+        Literal::u16_suffixed(index.try_into().unwrap())
+    }
+}
+
+fn parse_variants(data_enum: &DataEnum) -> syn::Result<Vec<(&Variant, Vec<FieldInfo>)>> {
+    data_enum
+        .variants
+        .iter()
+        .map(|variant| {
+            if let Some(attr) = redb_attributes(&variant.attrs).next() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "redb attributes are not supported on enum variants",
+                ));
+            }
+            Ok((variant, FieldInfo::parse_all(&variant.fields)?))
+        })
+        .collect()
+}
+
+fn generate_enum_type_name(
+    name: &Ident,
+    variants: &[(&Variant, Vec<FieldInfo>)],
+) -> proc_macro2::TokenStream {
+    let variant_strings: Vec<_> = variants
+        .iter()
+        .map(|(variant, fields)| generate_type_name(&variant.ident, &variant.fields, fields))
+        .collect();
+
+    quote! {
+        format!("enum {} {{{}}}",
+            stringify!(#name),
+            [#(#variant_strings),*].join(", ")
+        )
+    }
+}
+
+// Enums are fixed width if all of their variants are. Shorter variants are padded to the width of
+// the longest
+fn generate_enum_fixed_width(
+    variants: &[(&Variant, Vec<FieldInfo>)],
+    width: usize,
+) -> proc_macro2::TokenStream {
+    let variant_widths: Vec<_> = variants
+        .iter()
+        .map(|(_, fields)| stored_field_types(fields))
+        .filter(|field_types| !field_types.is_empty())
+        .map(|field_types| {
+            quote! {
+                {
+                    let mut variant_width = 0usize;
+                    #(
+                        variant_width += <#field_types>::fixed_width()?;
+                    )*
+                    max_width = max_width.max(variant_width);
+                }
+            }
+        })
+        .collect();
+
+    if variant_widths.is_empty() {
+        quote! { Some(#width) }
+    } else {
+        quote! {
+            let mut max_width = 0usize;
+            #(#variant_widths)*
+            Some(#width + max_width)
+        }
+    }
+}
+
+// Binds each stored field to `field_{index}`, and ignores skipped fields
+fn generate_variant_pattern(
+    name: &Ident,
+    variant: &Variant,
+    fields: &[FieldInfo],
+) -> proc_macro2::TokenStream {
+    let variant_name = &variant.ident;
+    let bindings: Vec<_> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            if field.skip.is_some() {
+                quote! { _ }
+            } else {
+                let var = field_var(i);
+                quote! { #var }
+            }
+        })
+        .collect();
+    match &variant.fields {
+        Fields::Named(_) => {
+            let field_names: Vec<_> = fields.iter().map(|field| &field.member).collect();
+            quote! { #name::#variant_name { #(#field_names: #bindings),* } }
+        }
+        Fields::Unnamed(_) => quote! { #name::#variant_name(#(#bindings),*) },
+        Fields::Unit => quote! { #name::#variant_name },
+    }
+}
+
+fn generate_enum_as_bytes(
+    name: &Ident,
+    variants: &[(&Variant, Vec<FieldInfo>)],
+    width: usize,
+) -> proc_macro2::TokenStream {
+    let arms: Vec<_> = variants
+        .iter()
+        .enumerate()
+        .map(|(index, (variant, fields))| {
+            let pattern = generate_variant_pattern(name, variant, fields);
+            let discriminant = discriminant_literal(width, index);
+            let field_types = stored_field_types(fields);
+            if field_types.is_empty() {
+                return quote! {
+                    #pattern => #discriminant.to_le_bytes().to_vec(),
+                };
+            }
+            let field_refs: Vec<_> = fields
+                .iter()
+                .enumerate()
+                .filter(|(_, field)| field.skip.is_none())
+                .map(|(i, _)| {
+                    let var = field_var(i);
+                    quote! { #var }
+                })
+                .collect();
+            let fields_as_bytes = generate_as_bytes(&field_types, &field_refs);
+            quote! {
+                #pattern => {
+                    let mut result = #discriminant.to_le_bytes().to_vec();
+                    result.extend_from_slice(&#fields_as_bytes);
+                    result
+                }
+            }
+        })
+        .collect();
+
+    quote! {
+        {
+            let mut result = match value {
+                #(#arms)*
+            };
+            if let Some(fixed_width) = <Self as redb::Value>::fixed_width() {
+                result.resize(fixed_width, 0);
+            }
+            result
+        }
+    }
+}
+
+fn generate_enum_from_bytes(
+    name: &Ident,
+    variants: &[(&Variant, Vec<FieldInfo>)],
+    width: usize,
+) -> proc_macro2::TokenStream {
+    let discriminant = generate_read_discriminant(width, &quote! { data });
+    let arms: Vec<_> = variants
+        .iter()
+        .enumerate()
+        .map(|(index, (variant, fields))| {
+            let variant_name = &variant.ident;
+            let literal = discriminant_literal(width, index);
+            let constructor =
+                generate_from_bytes(&quote! { #name::#variant_name }, &variant.fields, fields);
+            quote! {
+                #literal => #constructor,
+            }
+        })
+        .collect();
+    // Every possible discriminant is matched if the enum has exactly 256 or 65536 variants
+    let fallback = if variants.len() < 1 << (8 * width) {
+        quote! {
+            _ => unreachable!("invalid discriminant for {}", stringify!(#name)),
+        }
+    } else {
+        quote! {}
+    };
+
+    let skip_discriminant = if variants
+        .iter()
+        .all(|(_, fields)| stored_fields(fields).next().is_none())
+    {
+        quote! {}
+    } else {
+        quote! { let data = &data[#width..]; }
+    };
+
+    quote! {
+        {
+            let discriminant = #discriminant;
+            #skip_discriminant
+            match discriminant {
+                #(#arms)*
+                #fallback
+            }
         }
     }
 }
@@ -1,4 +1,4 @@
-use redb::{Database, Key, ReadableDatabase, TableDefinition, Value};
+use redb::{Database, Key, ReadableDatabase, ReadableTable, TableDefinition, Value};
 use redb_derive::{Key, Value};
 use std::fmt::Debug;
 use tempfile::NamedTempFile;
@@ -45,6 +45,166 @@ struct ComplexStruct<'inner, 'inner2> {
 #[derive(Value, Debug, PartialEq)]
 struct UnitStruct;
 
+#[derive(Key, Value, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum UnitEnum {
+    A,
+    B,
+    C,
+}
+
+#[derive(Key, Value, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+enum FixedEnum {
+    Empty,
+    Small(u8),
+    Large { a: u64, b: u32 },
+}
+
+#[derive(Key, Value, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum ComplexEnum<'inner> {
+    Id(u64),
+    Name {
+        first: &'inner str,
+        last: &'inner str,
+    },
+    Tagged(&'inner str, Option<u32>),
+    Nothing,
+}
+
+#[derive(Value, Debug, PartialEq)]
+#[redb(type_name = "Account")]
+struct AccountV1 {
+    id: u64,
+    balance: i64,
+}
+
+// A renamed copy of AccountV1, which keeps the same type name
+#[derive(Value, Debug, PartialEq)]
+#[redb(type_name = "Account")]
+struct RenamedAccount {
+    account_id: u64,
+    balance: i64,
+}
+
+fn default_label() -> String {
+    "unlabeled".to_string()
+}
+
+#[derive(Value, Debug, PartialEq)]
+struct SkippedFields {
+    id: u32,
+    #[redb(skip)]
+    cache: Vec<u8>,
+    #[redb(skip, default = "default_label")]
+    label: String,
+    name: String,
+}
+
+// Stores a u32 as eight hex digits
+#[derive(Debug)]
+struct HexU32;
+
+impl Value for HexU32 {
+    type SelfType<'a> = u32;
+    type AsBytes<'a> = Vec<u8>;
+
+    fn fixed_width() -> Option<usize> {
+        Some(8)
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> u32
+    where
+        Self: 'a,
+    {
+        u32::from_str_radix(std::str::from_utf8(data).unwrap(), 16).unwrap()
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a u32) -> Vec<u8>
+    where
+        Self: 'b,
+    {
+        format!("{value:08x}").into_bytes()
+    }
+
+    fn type_name() -> redb::TypeName {
+        redb::TypeName::new("HexU32")
+    }
+}
+
+#[derive(Value, Debug, PartialEq)]
+struct CustomEncoding(#[redb(with = "HexU32")] u32, bool);
+
+#[derive(Key, Value, Debug, PartialEq, Eq, Clone)]
+#[redb(order = [timestamp])]
+struct Event {
+    kind: String,
+    timestamp: u64,
+    id: u32,
+}
+
+#[derive(Key, Value, Debug, PartialEq, Eq, Clone)]
+#[redb(order = [1, 0])]
+struct Pair(String, u64);
+
+#[derive(Value, Debug, PartialEq)]
+enum CacheEntry {
+    Hit {
+        value: u64,
+        #[redb(skip)]
+        hits: u32,
+    },
+    Miss(#[redb(with = "HexU32")] u32),
+}
+
+// Stored before versioning was added. Its values have no version tag, so they are rewritten into
+// a table of the versioned type, rather than read by it
+#[derive(Value, Debug, PartialEq)]
+#[redb(type_name = "LegacyProfile")]
+struct LegacyProfile {
+    id: u32,
+    name: String,
+}
+
+#[derive(Value, Debug, PartialEq)]
+#[redb(version = 1, type_name = "Profile")]
+struct ProfileV1 {
+    name: String,
+}
+
+#[derive(Value, Debug, PartialEq)]
+#[redb(
+    version = 2,
+    type_name = "Profile",
+    previous = "ProfileV1",
+    upgrade = "upgrade_profile"
+)]
+struct ProfileV2 {
+    name: String,
+    age: Option<u8>,
+}
+
+fn upgrade_profile(previous: ProfileV1) -> ProfileV2 {
+    ProfileV2 {
+        name: previous.name,
+        age: None,
+    }
+}
+
+#[derive(Key, Value, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[redb(version = 3, type_name = "Profile", previous = "ProfileV2")]
+enum Profile {
+    Anonymous,
+    Named { name: String, age: Option<u8> },
+}
+
+impl From<ProfileV2> for Profile {
+    fn from(previous: ProfileV2) -> Self {
+        Profile::Named {
+            name: previous.name,
+            age: previous.age,
+        }
+    }
+}
+
 fn test_key_helper<K: Key + 'static>(key: &<K as Value>::SelfType<'_>) {
     let file = create_tempfile();
     let db = Database::create(file.path()).unwrap();
@@ -225,3 +385,404 @@ fn test_complex_struct() {
     test_key_helper::<ComplexStruct>(&original);
     test_value_helper::<ComplexStruct>(original, expected_name);
 }
+
+#[test]
+fn test_unit_enum() {
+    assert_eq!(UnitEnum::fixed_width(), Some(1));
+    assert_eq!(UnitEnum::as_bytes(&UnitEnum::B), vec![1]);
+    assert_eq!(UnitEnum::from_bytes(&[2]), UnitEnum::C);
+    test_key_helper::<UnitEnum>(&UnitEnum::A);
+    test_value_helper::<UnitEnum>(UnitEnum::C, "enum UnitEnum {A, B, C}");
+}
+
+#[test]
+fn test_fixed_enum() {
+    assert_eq!(FixedEnum::fixed_width(), Some(13));
+    let values = [
+        FixedEnum::Empty,
+        FixedEnum::Small(7),
+        FixedEnum::Large { a: 1, b: u32::MAX },
+    ];
+    for value in values {
+        let bytes = FixedEnum::as_bytes(&value);
+        assert_eq!(bytes.len(), 13);
+        assert_eq!(FixedEnum::from_bytes(&bytes), value);
+        test_key_helper::<FixedEnum>(&value);
+        test_value_helper::<FixedEnum>(
+            value,
+            "enum FixedEnum {Empty, Small(u8), Large {a: u64, b: u32}}",
+        );
+    }
+    let bytes = FixedEnum::as_bytes(&FixedEnum::Small(7));
+    assert_eq!(bytes[..2], [1, 7]);
+    assert!(bytes[2..].iter().all(|x| *x == 0));
+}
+
+#[test]
+fn test_complex_enum() {
+    assert_eq!(ComplexEnum::fixed_width(), None);
+    let expected_name = "enum ComplexEnum {Id(u64), Name {first: &str, last: &str}, Tagged(&str, Option<u32>), Nothing}";
+    let values = [
+        ComplexEnum::Id(42),
+        ComplexEnum::Name {
+            first: "Ada",
+            last: "Lovelace",
+        },
+        ComplexEnum::Tagged("tag", Some(3)),
+        ComplexEnum::Tagged("", None),
+        ComplexEnum::Nothing,
+    ];
+    for value in values {
+        let bytes = ComplexEnum::as_bytes(&value);
+        assert_eq!(ComplexEnum::from_bytes(&bytes), value);
+        test_key_helper::<ComplexEnum>(&value);
+        test_value_helper::<ComplexEnum>(value, expected_name);
+    }
+}
+
+#[test]
+fn test_enum_key_ordering() {
+    let values = [
+        ComplexEnum::Id(1),
+        ComplexEnum::Id(u64::MAX),
+        ComplexEnum::Name {
+            first: "b",
+            last: "a",
+        },
+        ComplexEnum::Name {
+            first: "b",
+            last: "b",
+        },
+        ComplexEnum::Tagged("a", None),
+        ComplexEnum::Tagged("a", Some(0)),
+        ComplexEnum::Nothing,
+    ];
+    for value1 in &values {
+        for value2 in &values {
+            assert_eq!(
+                ComplexEnum::compare(
+                    &ComplexEnum::as_bytes(value1),
+                    &ComplexEnum::as_bytes(value2)
+                ),
+                value1.cmp(value2)
+            );
+        }
+    }
+
+    let mut keys = vec![
+        FixedEnum::Large { a: 2, b: 0 },
+        FixedEnum::Small(u8::MAX),
+        FixedEnum::Empty,
+        FixedEnum::Large { a: 1, b: 5 },
+        FixedEnum::Small(0),
+    ];
+    let file = create_tempfile();
+    let db = Database::create(file.path()).unwrap();
+    let table_def: TableDefinition<FixedEnum, ()> = TableDefinition::new("test");
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(table_def).unwrap();
+        for key in &keys {
+            table.insert(key, ()).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    keys.sort();
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(table_def).unwrap();
+    let stored: Vec<FixedEnum> = table
+        .iter()
+        .unwrap()
+        .map(|x| x.unwrap().0.value())
+        .collect();
+    assert_eq!(stored, keys);
+}
+
+#[test]
+fn test_pinned_type_name() {
+    assert_eq!(AccountV1::type_name().name(), "Account");
+
+    let file = create_tempfile();
+    let db = Database::create(file.path()).unwrap();
+    let v1_def: TableDefinition<u32, AccountV1> = TableDefinition::new("accounts");
+    let renamed_def: TableDefinition<u32, RenamedAccount> = TableDefinition::new("accounts");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(v1_def).unwrap();
+        table.insert(0, AccountV1 { id: 7, balance: -3 }).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(renamed_def).unwrap();
+    assert_eq!(
+        table.get(0).unwrap().unwrap().value(),
+        RenamedAccount {
+            account_id: 7,
+            balance: -3
+        }
+    );
+}
+
+#[test]
+fn test_skipped_fields() {
+    let original = SkippedFields {
+        id: 1,
+        cache: vec![1, 2, 3],
+        label: "label".to_string(),
+        name: "name".to_string(),
+    };
+    let bytes = SkippedFields::as_bytes(&original);
+    let (id, name) = <(u32, String)>::from_bytes(&bytes);
+    assert_eq!(id, 1);
+    assert_eq!(name, "name");
+    assert_eq!(
+        SkippedFields::from_bytes(&bytes),
+        SkippedFields {
+            id: 1,
+            cache: vec![],
+            label: "unlabeled".to_string(),
+            name: "name".to_string(),
+        }
+    );
+    assert_eq!(
+        SkippedFields::type_name().name(),
+        "SkippedFields {id: u32, name: String}"
+    );
+}
+
+#[test]
+fn test_custom_encoding() {
+    let original = CustomEncoding(0xabc, true);
+    assert_eq!(CustomEncoding::fixed_width(), Some(9));
+    let bytes = CustomEncoding::as_bytes(&original);
+    assert_eq!(&bytes[..8], b"00000abc");
+    assert_eq!(CustomEncoding::from_bytes(&bytes), original);
+    test_value_helper::<CustomEncoding>(original, "CustomEncoding(HexU32, bool)");
+
+    let entries = [
+        CacheEntry::Hit { value: 5, hits: 0 },
+        CacheEntry::Miss(0xff),
+    ];
+    for entry in entries {
+        let bytes = CacheEntry::as_bytes(&entry);
+        assert_eq!(CacheEntry::from_bytes(&bytes), entry);
+    }
+    assert_eq!(
+        CacheEntry::from_bytes(&CacheEntry::as_bytes(&CacheEntry::Hit {
+            value: 5,
+            hits: 9
+        })),
+        CacheEntry::Hit { value: 5, hits: 0 }
+    );
+    assert_eq!(
+        CacheEntry::type_name().name(),
+        "enum CacheEntry {Hit {value: u64}, Miss(HexU32)}"
+    );
+}
+
+#[test]
+fn test_key_field_order() {
+    let mut events = vec![];
+    for (kind, timestamp, id) in [
+        ("b", 2, 0),
+        ("a", 2, 1),
+        ("a", 2, 0),
+        ("c", 1, 5),
+        ("a", 3, 0),
+    ] {
+        events.push(Event {
+            kind: kind.to_string(),
+            timestamp,
+            id,
+        });
+    }
+
+    let file = create_tempfile();
+    let db = Database::create(file.path()).unwrap();
+    let table_def: TableDefinition<Event, ()> = TableDefinition::new("events");
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(table_def).unwrap();
+        for event in &events {
+            table.insert(event, ()).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    events.sort_by(|a, b| (a.timestamp, &a.kind, a.id).cmp(&(b.timestamp, &b.kind, b.id)));
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(table_def).unwrap();
+    let stored: Vec<Event> = table
+        .iter()
+        .unwrap()
+        .map(|x| x.unwrap().0.value())
+        .collect();
+    assert_eq!(stored, events);
+
+    let pairs = [
+        Pair("a".to_string(), 2),
+        Pair("b".to_string(), 1),
+        Pair("a".to_string(), 1),
+    ];
+    for pair1 in &pairs {
+        for pair2 in &pairs {
+            assert_eq!(
+                Pair::compare(&Pair::as_bytes(pair1), &Pair::as_bytes(pair2)),
+                (pair1.1, &pair1.0).cmp(&(pair2.1, &pair2.0))
+            );
+        }
+    }
+}
+
+#[test]
+fn test_versioned_values() {
+    assert_eq!(ProfileV1::fixed_width(), None);
+    let bytes = ProfileV1::as_bytes(&ProfileV1 {
+        name: "a".to_string(),
+    });
+    assert_eq!(bytes, [1, 0, b'a']);
+
+    let file = create_tempfile();
+    let db = Database::create(file.path()).unwrap();
+    let v1_def: TableDefinition<u32, ProfileV1> = TableDefinition::new("profiles");
+    let v2_def: TableDefinition<u32, ProfileV2> = TableDefinition::new("profiles");
+    let def: TableDefinition<u32, Profile> = TableDefinition::new("profiles");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(v1_def).unwrap();
+        table
+            .insert(
+                1,
+                ProfileV1 {
+                    name: "one".to_string(),
+                },
+            )
+            .unwrap();
+    }
+    {
+        let mut table = write_txn.open_table(v2_def).unwrap();
+        table
+            .insert(
+                2,
+                ProfileV2 {
+                    name: "two".to_string(),
+                    age: Some(2),
+                },
+            )
+            .unwrap();
+    }
+    {
+        let mut table = write_txn.open_table(def).unwrap();
+        table.insert(3, Profile::Anonymous).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(def).unwrap();
+    let profiles: Vec<(u32, Profile)> = table
+        .iter()
+        .unwrap()
+        .map(|x| {
+            let (key, value) = x.unwrap();
+            (key.value(), value.value())
+        })
+        .collect();
+    assert_eq!(
+        profiles,
+        vec![
+            (
+                1,
+                Profile::Named {
+                    name: "one".to_string(),
+                    age: None
+                }
+            ),
+            (
+                2,
+                Profile::Named {
+                    name: "two".to_string(),
+                    age: Some(2)
+                }
+            ),
+            (3, Profile::Anonymous),
+        ]
+    );
+
+    // Keys of older versions are upgraded before they are compared
+    let old_key = ProfileV2::as_bytes(&ProfileV2 {
+        name: "b".to_string(),
+        age: None,
+    });
+    let new_key = Profile::as_bytes(&Profile::Named {
+        name: "a".to_string(),
+        age: None,
+    });
+    assert_eq!(
+        Profile::compare(&old_key, &new_key),
+        std::cmp::Ordering::Greater
+    );
+    assert_eq!(
+        Profile::compare(&Profile::as_bytes(&Profile::Anonymous), &old_key),
+        std::cmp::Ordering::Less
+    );
+}
+
+#[test]
+fn test_versioned_migration() {
+    // The first bytes of this legacy value look like a version tag of Profile
+    let legacy = LegacyProfile {
+        id: 0x6264_72F5,
+        name: "legacy".to_string(),
+    };
+    assert_eq!(LegacyProfile::as_bytes(&legacy)[0], 0xF5);
+
+    let file = create_tempfile();
+    let db = Database::create(file.path()).unwrap();
+    let legacy_def: TableDefinition<u32, LegacyProfile> = TableDefinition::new("legacy_profiles");
+    let def: TableDefinition<u32, Profile> = TableDefinition::new("profiles");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(legacy_def).unwrap();
+        table.insert(legacy.id, &legacy).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    // Values without a version tag are migrated once, by reading them with the legacy type and
+    // inserting them into a table of the versioned type
+    let write_txn = db.begin_write().unwrap();
+    {
+        let legacy_table = write_txn.open_table(legacy_def).unwrap();
+        let mut table = write_txn.open_table(def).unwrap();
+        for entry in legacy_table.iter().unwrap() {
+            let (key, value) = entry.unwrap();
+            let profile = Profile::Named {
+                name: value.value().name,
+                age: None,
+            };
+            table.insert(key.value(), profile).unwrap();
+        }
+    }
+    write_txn.delete_table(legacy_def).unwrap();
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(def).unwrap();
+    assert_eq!(
+        table.get(legacy.id).unwrap().unwrap().value(),
+        Profile::Named {
+            name: "legacy".to_string(),
+            age: None
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "corrupt value of Profile")]
+fn test_versioned_value_too_short() {
+    Profile::from_bytes(&[3]);
+}
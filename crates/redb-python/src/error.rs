@@ -15,6 +15,8 @@ create_exception!(redb, TransactionInProgress, DatabaseError);
 
 create_exception!(redb, Corrupted, StorageError);
 create_exception!(redb, ValueTooLarge, StorageError);
+create_exception!(redb, FileSizeLimitExceeded, StorageError);
+create_exception!(redb, SnapshotExpired, StorageError);
 create_exception!(redb, Io, StorageError);
 create_exception!(redb, PreviousIo, StorageError);
 create_exception!(redb, DatabaseClosed, StorageError);
@@ -42,6 +44,8 @@ pub(crate) fn map_storage_error(err: ::redb::StorageError) -> PyErr {
     match err {
         ::redb::StorageError::Corrupted(_) => Corrupted::new_err(msg),
         ::redb::StorageError::ValueTooLarge(_) => ValueTooLarge::new_err(msg),
+        ::redb::StorageError::FileSizeLimitExceeded(_) => FileSizeLimitExceeded::new_err(msg),
+        ::redb::StorageError::SnapshotExpired => SnapshotExpired::new_err(msg),
         ::redb::StorageError::Io(_) => Io::new_err(msg),
         ::redb::StorageError::PreviousIo => PreviousIo::new_err(msg),
         ::redb::StorageError::DatabaseClosed => DatabaseClosed::new_err(msg),
@@ -91,6 +95,11 @@ pub(crate) fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
     )?;
     m.add("Corrupted", m.py().get_type::<Corrupted>())?;
     m.add("ValueTooLarge", m.py().get_type::<ValueTooLarge>())?;
+    m.add(
+        "FileSizeLimitExceeded",
+        m.py().get_type::<FileSizeLimitExceeded>(),
+    )?;
+    m.add("SnapshotExpired", m.py().get_type::<SnapshotExpired>())?;
     m.add("Io", m.py().get_type::<Io>())?;
     m.add("PreviousIo", m.py().get_type::<PreviousIo>())?;
     m.add("DatabaseClosed", m.py().get_type::<DatabaseClosed>())?;
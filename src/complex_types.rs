@@ -1,4 +1,8 @@
-use crate::types::{TypeName, Value};
+use crate::types::{Key, TypeName, Value};
+use std::cmp::{Ordering, min};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::{BuildHasher, Hash};
+use std::ops::Range;
 
 // Encode len as a varint and store it at the end of output
 pub(super) fn encode_varint_len(len: usize, output: &mut Vec<u8>) {
@@ -31,6 +35,63 @@ pub(super) fn decode_varint_len(data: &[u8]) -> (usize, usize) {
     }
 }
 
+// Returns the range of the element which starts at `offset` in a serialized Vec<T>
+fn element_range<T: Value>(data: &[u8], mut offset: usize) -> Range<usize> {
+    let element_len = if let Some(len) = T::fixed_width() {
+        len
+    } else {
+        let (len, consumed) = decode_varint_len(&data[offset..]);
+        offset += consumed;
+        len
+    };
+    offset..(offset + element_len)
+}
+
+// Appends a serialized element of type T to `output`, preceded by its length if T is variable width
+fn encode_element<T: Value>(data: &[u8], output: &mut Vec<u8>) {
+    if T::fixed_width().is_none() {
+        encode_varint_len(data.len(), output);
+    }
+    output.extend_from_slice(data);
+}
+
+// Encodes the serialized entries of a map, or the keys of a set with V = (). The entries are sorted
+// by key, so that the encoding doesn't depend on the iteration order of the collection
+fn encode_entries<'a, K: Key + 'a, V: Value + 'a>(
+    mut entries: Vec<(K::AsBytes<'a>, V::AsBytes<'a>)>,
+) -> Vec<u8> {
+    entries.sort_by(|(key1, _), (key2, _)| K::compare(key1.as_ref(), key2.as_ref()));
+    let mut result = Vec::with_capacity(
+        entries
+            .iter()
+            .map(|(key, value)| key.as_ref().len() + value.as_ref().len() + 2)
+            .sum::<usize>()
+            + 5,
+    );
+    encode_varint_len(entries.len(), &mut result);
+    for (key, value) in &entries {
+        encode_element::<K>(key.as_ref(), &mut result);
+        encode_element::<V>(value.as_ref(), &mut result);
+    }
+    result
+}
+
+// Decodes the entries written by encode_entries()
+fn decode_entries<'a, K: Key + 'a, V: Value + 'a>(
+    data: &'a [u8],
+) -> impl Iterator<Item = (K::SelfType<'a>, V::SelfType<'a>)> {
+    let (entries, mut offset) = decode_varint_len(data);
+    (0..entries).map(move |_| {
+        let key_range = element_range::<K>(data, offset);
+        let value_range = element_range::<V>(data, key_range.end);
+        offset = value_range.end;
+        (
+            K::from_bytes(&data[key_range]),
+            V::from_bytes(&data[value_range]),
+        )
+    })
+}
+
 impl<T: Value> Value for Vec<T> {
     type SelfType<'a>
         = Vec<T::SelfType<'a>>
@@ -52,15 +113,9 @@ impl<T: Value> Value for Vec<T> {
         let (elements, mut offset) = decode_varint_len(data);
         let mut result = Vec::with_capacity(elements);
         for _ in 0..elements {
-            let element_len = if let Some(len) = T::fixed_width() {
-                len
-            } else {
-                let (len, consumed) = decode_varint_len(&data[offset..]);
-                offset += consumed;
-                len
-            };
-            result.push(T::from_bytes(&data[offset..(offset + element_len)]));
-            offset += element_len;
+            let range = element_range::<T>(data, offset);
+            offset = range.end;
+            result.push(T::from_bytes(&data[range]));
         }
         assert_eq!(offset, data.len());
         result
@@ -78,11 +133,7 @@ impl<T: Value> Value for Vec<T> {
         encode_varint_len(value.len(), &mut result);
 
         for element in value {
-            let serialized = T::as_bytes(element);
-            if T::fixed_width().is_none() {
-                encode_varint_len(serialized.as_ref().len(), &mut result);
-            }
-            result.extend_from_slice(serialized.as_ref());
+            encode_element::<T>(T::as_bytes(element).as_ref(), &mut result);
         }
         result
     }
@@ -91,3 +142,195 @@ impl<T: Value> Value for Vec<T> {
         TypeName::internal(&format!("Vec<{}>", T::type_name().name()))
     }
 }
+
+// Compares element-wise, and then by length, so that a Vec sorts before any longer Vec that it is
+// a prefix of
+impl<T: Key> Key for Vec<T> {
+    fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+        let (len1, mut offset1) = decode_varint_len(data1);
+        let (len2, mut offset2) = decode_varint_len(data2);
+        for _ in 0..min(len1, len2) {
+            let range1 = element_range::<T>(data1, offset1);
+            let range2 = element_range::<T>(data2, offset2);
+            offset1 = range1.end;
+            offset2 = range2.end;
+            let comparison = T::compare(&data1[range1], &data2[range2]);
+            if !comparison.is_eq() {
+                return comparison;
+            }
+        }
+        len1.cmp(&len2)
+    }
+}
+
+impl<K: Key, V: Value> Value for BTreeMap<K, V>
+where
+    for<'a> K::SelfType<'a>: Ord,
+{
+    type SelfType<'a>
+        = BTreeMap<K::SelfType<'a>, V::SelfType<'a>>
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> BTreeMap<K::SelfType<'a>, V::SelfType<'a>>
+    where
+        Self: 'a,
+    {
+        decode_entries::<K, V>(data).collect()
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a BTreeMap<K::SelfType<'b>, V::SelfType<'b>>) -> Vec<u8>
+    where
+        Self: 'b,
+    {
+        encode_entries::<K, V>(
+            value
+                .iter()
+                .map(|(key, value)| (K::as_bytes(key), V::as_bytes(value)))
+                .collect(),
+        )
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::internal(&format!(
+            "BTreeMap<{},{}>",
+            K::type_name().name(),
+            V::type_name().name()
+        ))
+    }
+}
+
+impl<K: Key, V: Value, S: BuildHasher + Default + 'static> Value for HashMap<K, V, S>
+where
+    for<'a> K::SelfType<'a>: Eq + Hash,
+{
+    type SelfType<'a>
+        = HashMap<K::SelfType<'a>, V::SelfType<'a>, S>
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> HashMap<K::SelfType<'a>, V::SelfType<'a>, S>
+    where
+        Self: 'a,
+    {
+        decode_entries::<K, V>(data).collect()
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a HashMap<K::SelfType<'b>, V::SelfType<'b>, S>) -> Vec<u8>
+    where
+        Self: 'b,
+    {
+        encode_entries::<K, V>(
+            value
+                .iter()
+                .map(|(key, value)| (K::as_bytes(key), V::as_bytes(value)))
+                .collect(),
+        )
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::internal(&format!(
+            "HashMap<{},{}>",
+            K::type_name().name(),
+            V::type_name().name()
+        ))
+    }
+}
+
+impl<T: Key> Value for BTreeSet<T>
+where
+    for<'a> T::SelfType<'a>: Ord,
+{
+    type SelfType<'a>
+        = BTreeSet<T::SelfType<'a>>
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> BTreeSet<T::SelfType<'a>>
+    where
+        Self: 'a,
+    {
+        decode_entries::<T, ()>(data).map(|(key, ())| key).collect()
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a BTreeSet<T::SelfType<'b>>) -> Vec<u8>
+    where
+        Self: 'b,
+    {
+        encode_entries::<T, ()>(
+            value
+                .iter()
+                .map(|key| (T::as_bytes(key), &[][..]))
+                .collect(),
+        )
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::internal(&format!("BTreeSet<{}>", T::type_name().name()))
+    }
+}
+
+impl<T: Key, S: BuildHasher + Default + 'static> Value for HashSet<T, S>
+where
+    for<'a> T::SelfType<'a>: Eq + Hash,
+{
+    type SelfType<'a>
+        = HashSet<T::SelfType<'a>, S>
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> HashSet<T::SelfType<'a>, S>
+    where
+        Self: 'a,
+    {
+        decode_entries::<T, ()>(data).map(|(key, ())| key).collect()
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a HashSet<T::SelfType<'b>, S>) -> Vec<u8>
+    where
+        Self: 'b,
+    {
+        encode_entries::<T, ()>(
+            value
+                .iter()
+                .map(|key| (T::as_bytes(key), &[][..]))
+                .collect(),
+        )
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::internal(&format!("HashSet<{}>", T::type_name().name()))
+    }
+}
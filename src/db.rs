@@ -1,27 +1,31 @@
-use crate::transaction_tracker::{TransactionId, TransactionTracker};
+use crate::transaction_tracker::{ReadTransactionWarning, TransactionId, TransactionTracker};
 use crate::tree_store::{
-    AllocationPolicy, BtreeHeader, InternalTableDefinition, PAGE_SIZE, PageHint, PageNumber,
-    PageResolver, ReadOnlyBackend, ShrinkPolicy, TableTree, TableType, TransactionalMemory,
+    AllocationPolicy, BtreeHeader, CachePolicy, InternalTableDefinition, MAX_PAGE_SIZE,
+    MIN_PAGE_SIZE, PAGE_SIZE, PageHint, PageNumber, PageResolver, ReadOnlyBackend, ShrinkPolicy,
+    TableTree, TableType, TransactionalMemory,
 };
 use crate::types::{Key, Value};
 use crate::{
-    CompactionError, DatabaseError, Error, ReadOnlyTable, SavepointError, StorageError, TableError,
+    CompactionError, DatabaseError, Error, IoStats, ReadOnlyTable, SavepointError, StorageError,
+    TableError,
 };
-use crate::{ReadTransaction, Result, WriteTransaction};
+use crate::{ReadTransaction, ReadTransactionInfo, Result, WriteTransaction};
 use std::fmt::{Debug, Display, Formatter};
 
 use std::fs::{File, OpenOptions};
 use std::marker::PhantomData;
 use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
 use std::{io, thread};
 
 use crate::error::TransactionError;
 use crate::sealed::Sealed;
 use crate::transactions::{
     ALLOCATOR_STATE_TABLE_NAME, AllocatorStateKey, AllocatorStateTree, DATA_ALLOCATED_TABLE,
-    DATA_FREED_TABLE, PageList, SYSTEM_FREED_TABLE, SystemTableDefinition,
-    TransactionIdWithPagination,
+    DATA_FREED_TABLE, MAX_PAGES_PER_COMPACTION, PageList, SYSTEM_FREED_TABLE,
+    SystemTableDefinition, TransactionIdWithPagination,
 };
 use crate::tree_store::file_backend::FileBackend;
 #[cfg(feature = "logging")]
@@ -275,6 +279,11 @@ pub(crate) enum TransactionGuard {
     Read {
         tracker: Arc<TransactionTracker>,
         transaction_id: TransactionId,
+        handle: u64,
+        // Set if the transaction exceeds the maximum read transaction age
+        expired: Arc<AtomicBool>,
+        #[cfg(debug_assertions)]
+        mem: Arc<TransactionalMemory>,
     },
     Write {
         tracker: Arc<TransactionTracker>,
@@ -286,22 +295,19 @@ pub(crate) enum TransactionGuard {
 }
 
 impl TransactionGuard {
-    pub(crate) fn new_read(
-        transaction_id: TransactionId,
-        tracker: Arc<TransactionTracker>,
-    ) -> Self {
-        Self::Read {
-            tracker,
-            transaction_id,
-        }
-    }
-
     pub(crate) fn allocate_read(
         tracker: Arc<TransactionTracker>,
-        mem: &TransactionalMemory,
+        mem: &Arc<TransactionalMemory>,
     ) -> Result<Self> {
-        let id = tracker.register_read_transaction(mem)?;
-        Ok(Self::new_read(id, tracker))
+        let (transaction_id, handle, expired) = tracker.begin_user_read_transaction(mem)?;
+        Ok(Self::Read {
+            tracker,
+            transaction_id,
+            handle,
+            expired,
+            #[cfg(debug_assertions)]
+            mem: mem.clone(),
+        })
     }
 
     pub(crate) fn new_write(
@@ -328,6 +334,13 @@ impl TransactionGuard {
             }
         }
     }
+
+    pub(crate) fn snapshot_expired(&self) -> Option<Arc<AtomicBool>> {
+        match self {
+            Self::Read { expired, .. } => Some(expired.clone()),
+            Self::Write { .. } | Self::Untracked => None,
+        }
+    }
 }
 
 impl Drop for TransactionGuard {
@@ -336,13 +349,21 @@ impl Drop for TransactionGuard {
             Self::Read {
                 tracker,
                 transaction_id,
-            } => tracker.deallocate_read_transaction(*transaction_id),
+                handle,
+                ..
+            } => tracker.end_user_read_transaction(*transaction_id, *handle),
             Self::Write {
                 tracker,
                 transaction_id,
             } => tracker.end_write_transaction(*transaction_id),
             Self::Untracked => {}
         }
+        #[cfg(debug_assertions)]
+        if let Self::Read { expired, mem, .. } = self
+            && expired.load(std::sync::atomic::Ordering::Acquire)
+        {
+            mem.expired_read_transaction_dropped();
+        }
     }
 }
 
@@ -362,6 +383,41 @@ pub trait ReadableDatabase {
     fn cache_stats(&self) -> CacheStats;
 }
 
+// Implements `Database::warm_cache()` and `ReadOnlyDatabase::warm_cache()`
+fn warm_cache(
+    txn: &ReadTransaction,
+    tables: &[&dyn TableHandle],
+    multimap_tables: &[&dyn MultimapTableHandle],
+    byte_budget: u64,
+    leaf_pages: bool,
+) -> Result<(), Error> {
+    let mut budget = byte_budget;
+    let mut leaves = vec![];
+    for handle in tables {
+        let table = txn.open_untyped_table(UntypedTableHandle::new(handle.name().to_string()))?;
+        let table_leaves = table.prefetch_branches(&mut budget)?;
+        leaves.push((table, table_leaves));
+    }
+    let mut multimap_leaves = vec![];
+    for handle in multimap_tables {
+        let table = txn.open_untyped_multimap_table(UntypedMultimapTableHandle::new(
+            handle.name().to_string(),
+        ))?;
+        let table_leaves = table.prefetch_branches(&mut budget)?;
+        multimap_leaves.push((table, table_leaves));
+    }
+    if leaf_pages {
+        for (table, table_leaves) in leaves {
+            table.prefetch_pages(&table_leaves, &mut budget)?;
+        }
+        for (table, table_leaves) in multimap_leaves {
+            table.prefetch_pages(&table_leaves, &mut budget)?;
+        }
+    }
+
+    Ok(())
+}
+
 /// A redb database opened in read-only mode
 ///
 /// Use [`Self::begin_read`] to get a [`ReadTransaction`] object that can be used to read from the database
@@ -409,13 +465,11 @@ pub struct ReadOnlyDatabase {
 
 impl ReadableDatabase for ReadOnlyDatabase {
     fn begin_read(&self) -> Result<ReadTransaction, TransactionError> {
-        let id = self
-            .transaction_tracker
-            .register_read_transaction(&self.mem)?;
+        self.transaction_tracker
+            .check_read_transaction_ages(&self.mem);
+        let guard = TransactionGuard::allocate_read(self.transaction_tracker.clone(), &self.mem)?;
         #[cfg(feature = "logging")]
-        debug!("Beginning read transaction id={id:?}");
-
-        let guard = TransactionGuard::new_read(id, self.transaction_tracker.clone());
+        debug!("Beginning read transaction id={:?}", guard.id());
 
         ReadTransaction::new(self.mem.clone(), guard)
     }
@@ -431,12 +485,46 @@ impl ReadOnlyDatabase {
         Builder::new().open_read_only(path)
     }
 
-    fn new(
-        file: Box<dyn StorageBackend>,
-        page_size: usize,
-        region_size: Option<u64>,
-        cache_size: usize,
-    ) -> Result<Self, DatabaseError> {
+    /// Reads pages of the given tables into the cache. See [`Database::warm_cache`]
+    pub fn warm_cache(
+        &self,
+        tables: &[&dyn TableHandle],
+        multimap_tables: &[&dyn MultimapTableHandle],
+        byte_budget: u64,
+        leaf_pages: bool,
+    ) -> Result<(), Error> {
+        warm_cache(
+            &self.begin_read()?,
+            tables,
+            multimap_tables,
+            byte_budget,
+            leaf_pages,
+        )
+    }
+
+    /// Pins the branch pages of the given table in the cache. See [`Database::pin_table_branches`]
+    pub fn pin_table_branches(&self, table: impl TableHandle) {
+        self.mem.pin_table_branches(table.name());
+    }
+
+    /// Allows the branch pages of the given table to be evicted from the cache again
+    pub fn unpin_table_branches(&self, table: impl TableHandle) {
+        self.mem.unpin_table_branches(table.name());
+    }
+
+    /// Returns a snapshot of the I/O statistics collected since the database was opened. See
+    /// [`Database::io_stats`]
+    pub fn io_stats(&self) -> IoStats {
+        self.mem.io_stats()
+    }
+
+    /// Returns the read transactions which are currently live, oldest first. See
+    /// [`Database::live_read_transactions`]
+    pub fn live_read_transactions(&self) -> Vec<ReadTransactionInfo> {
+        self.transaction_tracker.live_read_transactions()
+    }
+
+    fn new(file: Box<dyn StorageBackend>, config: &Builder) -> Result<Self, DatabaseError> {
         #[cfg(feature = "logging")]
         let file_path = format!("{:?}", &file);
         #[cfg(feature = "logging")]
@@ -444,11 +532,14 @@ impl ReadOnlyDatabase {
         let mem = TransactionalMemory::new(
             Box::new(ReadOnlyBackend::new(file)),
             false,
-            page_size,
-            region_size,
-            cache_size,
+            config.page_size,
+            None,
+            config.cache_size,
+            config.cache_policy,
+            0,
             true,
         )?;
+        mem.set_table_io_stats(config.table_io_stats);
         let mem = Arc::new(mem);
         // If the last transaction used 2-phase commit and updated the allocator state table, then
         // we can just load the allocator state from there. Otherwise, we need a full repair
@@ -466,7 +557,13 @@ impl ReadOnlyDatabase {
         let next_transaction_id = mem.get_last_committed_transaction_id()?.next();
         let db = Self {
             mem,
-            transaction_tracker: Arc::new(TransactionTracker::new(next_transaction_id)),
+            transaction_tracker: Arc::new(
+                TransactionTracker::new(next_transaction_id).with_read_transaction_monitoring(
+                    config.read_transaction_backtraces,
+                    config.read_transaction_warning.clone(),
+                    config.max_read_transaction_age,
+                ),
+            ),
         };
 
         Ok(db)
@@ -513,6 +610,8 @@ pub struct Database {
 
 impl ReadableDatabase for Database {
     fn begin_read(&self) -> Result<ReadTransaction, TransactionError> {
+        self.transaction_tracker
+            .check_read_transaction_ages(&self.mem);
         let guard = TransactionGuard::allocate_read(self.transaction_tracker.clone(), &self.mem)?;
         #[cfg(feature = "logging")]
         debug!("Beginning read transaction id={:?}", guard.id());
@@ -538,6 +637,61 @@ impl Database {
         Self::builder().open(path)
     }
 
+    /// Reads pages of the given tables into the cache, so that the first reads after opening the
+    /// database are fast
+    ///
+    /// The branch pages of all the tables are read first, starting from the root of each table.
+    /// Then, if `leaf_pages` is true, the leaf pages are read in key order. Reading stops once
+    /// `byte_budget` bytes have been read. The budget should not exceed the cache size set with
+    /// [`Builder::set_cache_size`], as otherwise the pages read last may evict earlier ones.
+    ///
+    /// For multimap tables, the pages which map keys to their values are read, but not the
+    /// separate trees used to store a key's values once there are many of them.
+    pub fn warm_cache(
+        &self,
+        tables: &[&dyn TableHandle],
+        multimap_tables: &[&dyn MultimapTableHandle],
+        byte_budget: u64,
+        leaf_pages: bool,
+    ) -> Result<(), Error> {
+        warm_cache(
+            &self.begin_read()?,
+            tables,
+            multimap_tables,
+            byte_budget,
+            leaf_pages,
+        )
+    }
+
+    /// Pins the branch pages of the given table in the cache, so that they are never evicted
+    ///
+    /// Pages are pinned as they are read by read transactions, so [`Database::warm_cache`]
+    /// can be used to pin them all up front. Pinned pages count towards the cache size. This setting
+    /// is not persisted, and only applies to this instance of the database.
+    pub fn pin_table_branches(&self, table: impl TableHandle) {
+        self.mem.pin_table_branches(table.name());
+    }
+
+    /// Allows the branch pages of the given table to be evicted from the cache again
+    pub fn unpin_table_branches(&self, table: impl TableHandle) {
+        self.mem.unpin_table_branches(table.name());
+    }
+
+    /// Returns a snapshot of the I/O statistics collected since the database was opened
+    ///
+    /// Per-table statistics are only collected if enabled with [`Builder::set_table_io_stats`]
+    pub fn io_stats(&self) -> IoStats {
+        self.mem.io_stats()
+    }
+
+    /// Returns the read transactions which are currently live, oldest first
+    ///
+    /// Live read transactions prevent the pages freed by later write transactions from being
+    /// reused. Savepoints are not included.
+    pub fn live_read_transactions(&self) -> Vec<ReadTransactionInfo> {
+        self.transaction_tracker.live_read_transactions()
+    }
+
     pub(crate) fn get_memory(&self) -> Arc<TransactionalMemory> {
         self.mem.clone()
     }
@@ -725,6 +879,8 @@ impl Database {
     ///
     /// Returns `true` if compaction was performed, and `false` if no futher compaction was possible
     pub fn compact(&mut self) -> Result<bool, CompactionError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("compact").entered();
         // These checks must run before begin_write(): the caller may legally hold an open
         // WriteTransaction (it is not lifetime-bound to the Database), and if that transaction
         // created a savepoint, blocking in begin_write() below would deadlock. Savepoints must
@@ -765,7 +921,7 @@ impl Database {
             let mut progress = false;
 
             let mut txn = self.begin_write().map_err(|e| e.into_storage_error())?;
-            if txn.compact_pages()? {
+            if txn.compact_pages(MAX_PAGES_PER_COMPACTION)? {
                 progress = true;
                 txn.commit().map_err(|e| e.into_storage_error())?;
             } else {
@@ -786,7 +942,38 @@ impl Database {
         Ok(compacted)
     }
 
+    /// Performs one bounded step of compaction
+    ///
+    /// Relocates up to `max_pages` pages from the end of the database file, along with the branch
+    /// pages that reference them, to free space closer to its start. Each step runs in its own
+    /// write transaction, so unlike [`Self::compact`], it does not require exclusive access: read
+    /// transactions and savepoints may be live, and other write transactions run between steps.
+    ///
+    /// The file is shrunk as space at its end becomes free. Pages which are still referenced by a
+    /// live read transaction or savepoint are not freed until it is dropped, so compaction may make
+    /// little progress while an old read transaction or savepoint exists.
+    ///
+    /// Each step begins a write transaction, so it blocks until any write in progress completes.
+    /// It must not be called while the calling thread holds a [`WriteTransaction`] on this
+    /// database, as that deadlocks.
+    ///
+    /// Returns `true` if any pages were relocated. Call this repeatedly until it returns `false`
+    pub fn compact_step(&self, max_pages: usize) -> Result<bool, CompactionError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("compact_step", max_pages).entered();
+        let mut txn = self.begin_write().map_err(|e| e.into_storage_error())?;
+        // The previous step freed the pages that it relocated, if no read transaction needs them,
+        // so the space at the end of the file can now be trimmed
+        txn.set_shrink_policy(ShrinkPolicy::Maximum);
+        let progress = txn.compact_pages(max_pages)?;
+        txn.commit().map_err(|e| e.into_storage_error())?;
+
+        Ok(progress)
+    }
+
     fn drain_pending_free_pages(&self, shrink_policy: ShrinkPolicy) -> Result {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("drain_pending_free_pages").entered();
         // Preserve compact()'s empty durable commit, which also publishes pending
         // non-durable roots before checking for pending frees.
         let mut force_commit = true;
@@ -950,6 +1137,8 @@ impl Database {
         mem: &mut Arc<TransactionalMemory>, // Only &mut to ensure exclusivity
         repair_callback: &(dyn Fn(&mut RepairSession) + 'static),
     ) -> Result<[Option<BtreeHeader>; 2], DatabaseError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("repair").entered();
         if !Self::verify_primary_checksums(mem.clone())? {
             if mem.used_two_phase_commit() {
                 return Err(DatabaseError::Storage(StorageError::Corrupted(
@@ -1058,11 +1247,9 @@ impl Database {
     fn new(
         file: Box<dyn StorageBackend>,
         allow_initialize: bool,
-        page_size: usize,
-        region_size: Option<u64>,
-        cache_size: usize,
-        repair_callback: &(dyn Fn(&mut RepairSession) + 'static),
+        config: &Builder,
     ) -> Result<Self, DatabaseError> {
+        let repair_callback = &config.repair_callback;
         #[cfg(feature = "logging")]
         let file_path = format!("{:?}", &file);
         #[cfg(feature = "logging")]
@@ -1070,11 +1257,16 @@ impl Database {
         let mem = TransactionalMemory::new(
             file,
             allow_initialize,
-            page_size,
-            region_size,
-            cache_size,
+            config.page_size,
+            config.region_size,
+            config.cache_size,
+            config.cache_policy,
+            config.growth_step,
             false,
         )?;
+        mem.set_max_file_len(config.max_file_size);
+        mem.set_dirty_page_budget(config.dirty_page_budget);
+        mem.set_table_io_stats(config.table_io_stats);
         let mut mem = Arc::new(mem);
         // If the last transaction used 2-phase commit and updated the allocator state table, then
         // we can just load the allocator state from there. Otherwise, we need a full repair
@@ -1108,7 +1300,13 @@ impl Database {
 
         let db = Database {
             mem,
-            transaction_tracker: Arc::new(TransactionTracker::new(next_transaction_id)),
+            transaction_tracker: Arc::new(
+                TransactionTracker::new(next_transaction_id).with_read_transaction_monitoring(
+                    config.read_transaction_backtraces,
+                    config.read_transaction_warning.clone(),
+                    config.max_read_transaction_age,
+                ),
+            ),
         };
 
         // Restore the tracker state for any persistent savepoints
@@ -1133,6 +1331,15 @@ impl Database {
         }
         txn.abort()?;
 
+        if config.preallocate > 0 {
+            let txn = db.begin_write().map_err(|e| e.into_storage_error())?;
+            if db.mem.set_min_file_len(config.preallocate)? {
+                txn.commit().map_err(|e| e.into_storage_error())?;
+            } else {
+                txn.abort()?;
+            }
+        }
+
         Ok(db)
     }
 
@@ -1183,6 +1390,27 @@ impl Database {
         Builder::new()
     }
 
+    /// Grows the database file, so that at least `additional` bytes are available for new data
+    /// without any further growth
+    ///
+    /// The file will not be shrunk below the resulting length while this [`Database`] is open,
+    /// including by [`Database::compact`]. If a write is in progress, this function will block
+    /// until it completes.
+    ///
+    /// Returns [`StorageError::FileSizeLimitExceeded`] if the file would have to grow past the
+    /// limit set with [`Builder::set_max_file_size`].
+    pub fn reserve(&self, additional: u64) -> Result<(), Error> {
+        let txn = self.begin_write()?;
+        let len = self.mem.file_len_with_free_space(additional)?;
+        if self.mem.set_min_file_len(len)? {
+            txn.commit()?;
+        } else {
+            txn.abort()?;
+        }
+
+        Ok(())
+    }
+
     /// Begins a write transaction
     ///
     /// Returns a [`WriteTransaction`] which may be used to read/write to the database. Only a single
@@ -1198,12 +1426,17 @@ impl Database {
         &self,
         allocation_policy: AllocationPolicy,
     ) -> Result<WriteTransaction, TransactionError> {
+        #[cfg(feature = "tracing")]
+        let span =
+            tracing::info_span!("begin_write", transaction_id = tracing::field::Empty).entered();
         // Fail early if there has been an I/O error -- nothing can be committed in that case
         self.mem.check_io_errors()?;
-        let guard = TransactionGuard::new_write(
-            self.transaction_tracker.start_write_transaction(),
-            self.transaction_tracker.clone(),
-        );
+        self.transaction_tracker
+            .check_read_transaction_ages(&self.mem);
+        let transaction_id = self.transaction_tracker.start_write_transaction();
+        #[cfg(feature = "tracing")]
+        span.record("transaction_id", transaction_id.raw_id());
+        let guard = TransactionGuard::new_write(transaction_id, self.transaction_tracker.clone());
         WriteTransaction::new(
             guard,
             self.transaction_tracker.clone(),
@@ -1278,6 +1511,15 @@ pub struct Builder {
     page_size: usize,
     region_size: Option<u64>,
     cache_size: usize,
+    cache_policy: CachePolicy,
+    preallocate: u64,
+    growth_step: u64,
+    max_file_size: Option<u64>,
+    dirty_page_budget: Option<usize>,
+    table_io_stats: bool,
+    read_transaction_backtraces: bool,
+    read_transaction_warning: Option<ReadTransactionWarning>,
+    max_read_transaction_age: Option<Duration>,
     repair_callback: Box<dyn Fn(&mut RepairSession)>,
 }
 
@@ -1287,15 +1529,24 @@ impl Builder {
     /// ## Defaults
     ///
     /// - `cache_size_bytes`: 1GiB
+    /// - `page_size`: 4KiB
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         Self {
             // Default to 4k pages. Benchmarking showed that this was a good default on all platforms,
-            // including MacOS with 16k pages. Therefore, users are not allowed to configure it at the moment.
-            // It is part of the file format, so can be enabled in the future.
+            // including MacOS with 16k pages.
             page_size: PAGE_SIZE,
             region_size: None,
             cache_size: 1024 * 1024 * 1024,
+            cache_policy: CachePolicy::Lru,
+            preallocate: 0,
+            growth_step: 0,
+            max_file_size: None,
+            dirty_page_budget: None,
+            table_io_stats: false,
+            read_transaction_backtraces: false,
+            read_transaction_warning: None,
+            max_read_transaction_age: None,
             repair_callback: Box::new(|_| {}),
         }
     }
@@ -1317,15 +1568,27 @@ impl Builder {
 
     /// Set the internal page size of the database
     ///
-    /// Valid values are powers of two, greater than or equal to 512
+    /// Valid values are powers of two, from 512 bytes to 64KiB. Larger pages waste less space when
+    /// storing values that are a significant fraction of a page, and reduce the height of the
+    /// B-trees. Smaller pages reduce the number of bytes written by each modification.
+    ///
+    /// The page size is part of the file format, and is fixed when the database is created. When an
+    /// existing database is opened, the page size is read from the file, and this setting is
+    /// ignored.
     ///
     /// ## Defaults
     ///
     /// Default to 4 Kib pages.
-    #[cfg(any(fuzzing, test))]
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is not a power of two between 512 and 64KiB
     pub fn set_page_size(&mut self, size: usize) -> &mut Self {
-        assert!(size.is_power_of_two());
-        self.page_size = std::cmp::max(size, 512);
+        assert!(
+            size.is_power_of_two() && (MIN_PAGE_SIZE..=MAX_PAGE_SIZE).contains(&size),
+            "page size must be a power of two between {MIN_PAGE_SIZE} and {MAX_PAGE_SIZE}, found {size}"
+        );
+        self.page_size = size;
         self
     }
 
@@ -1335,13 +1598,185 @@ impl Builder {
         self
     }
 
-    #[cfg(any(test, fuzzing))]
+    /// Set the policy used to choose which data to evict from the cache, when it is full
+    ///
+    /// [`CachePolicy::TwoQueue`] and [`CachePolicy::Adaptive`] prevent large scans from evicting
+    /// frequently read data.
+    ///
+    /// ## Defaults
+    ///
+    /// Default to [`CachePolicy::Lru`].
+    pub fn set_cache_policy(&mut self, policy: CachePolicy) -> &mut Self {
+        self.cache_policy = policy;
+        self
+    }
+
+    /// Set the size of the regions that the database file is divided into
+    ///
+    /// Once the database is larger than a single region, the file grows one region at a time,
+    /// and each region has its own page allocator. Smaller regions make growth more gradual, but
+    /// a single key-value pair must fit within a region; inserting a larger one returns
+    /// [`StorageError::ValueTooLarge`].
+    ///
+    /// The region size is capped at 4GiB, and at 2^20 pages. A region must also be able to hold
+    /// the allocator state, so smaller sizes are rounded up to 4KiB, or to one page if the page
+    /// size is larger. It is part of the file format, so it only takes effect when a new database
+    /// is created.
+    ///
+    /// ## Defaults
+    ///
+    /// Default to the largest supported region size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is not a power of two
     pub fn set_region_size(&mut self, size: u64) -> &mut Self {
         assert!(size.is_power_of_two());
         self.region_size = Some(size);
         self
     }
 
+    /// Preallocate the database file, so that it is at least `bytes` long
+    ///
+    /// The file is grown when the database is opened, if it is shorter than `bytes`, and is not
+    /// shrunk below `bytes` while the database is open. See also [`Database::reserve`].
+    ///
+    /// ## Defaults
+    ///
+    /// Default to no preallocation.
+    pub fn set_preallocate(&mut self, bytes: u64) -> &mut Self {
+        self.preallocate = bytes;
+        self
+    }
+
+    /// Set the minimum number of bytes by which the database file is grown, when it runs out of
+    /// space
+    ///
+    /// A larger step reduces the number of times that the file has to be extended, which can be
+    /// expensive on some filesystems. The file is not automatically shrunk below one growth step
+    /// of free space, though [`Database::compact`] may still release it.
+    ///
+    /// ## Defaults
+    ///
+    /// Default to 0, in which case the file grows by doubling until it reaches the region size,
+    /// and then by one region at a time.
+    pub fn set_growth_step(&mut self, bytes: u64) -> &mut Self {
+        self.growth_step = bytes;
+        self
+    }
+
+    /// Set the maximum size (in bytes) of the database file
+    ///
+    /// The file is never grown past this size. Writes which would need it to grow further fail with
+    /// [`StorageError::FileSizeLimitExceeded`]. Room for the metadata written by commit is held
+    /// back below the limit, and before a commit changes anything, it checks that its metadata fits.
+    /// If not, the transaction is aborted and the commit returns the same error, so a commit never
+    /// fails partway through for lack of space.
+    ///
+    /// Insertions and removals check for space before they start, so when one fails, it has no
+    /// effect, and the transaction remains usable: the caller may remove data and commit, or abort
+    /// it. An operation which runs out of space after it has started, as `retain()` and
+    /// `extract_if()` may, or one which needs more space than its check estimated, leaves the
+    /// transaction unable to commit: commit aborts it and returns an error.
+    ///
+    /// Space freed by removing data that was committed by an earlier transaction only becomes
+    /// available once the removal has been committed, and no read transaction references the old
+    /// data. Recording the freed pages takes space too, so a file at its limit may need data to be
+    /// removed in several smaller transactions. A database file which is already larger than the
+    /// limit is not shrunk, and neither is a new one, which starts at about 1MiB.
+    ///
+    /// ## Defaults
+    ///
+    /// Default to no limit.
+    pub fn set_max_file_size(&mut self, bytes: u64) -> &mut Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Set the maximum number of bytes of modified pages that a write transaction buffers in memory
+    ///
+    /// When a write transaction exceeds the budget, its least recently used modified pages are
+    /// written early to their allocated locations in the file. This does not affect crash safety,
+    /// because those pages are not reachable from the last committed transaction. The current
+    /// usage is reported by [`WriteTransaction::dirty_bytes`].
+    ///
+    /// The budget can only lower the default: it is capped at half of the cache size, set with
+    /// [`Builder::set_cache_size`], so a larger value has no effect. Raise the cache size to allow
+    /// a larger budget.
+    ///
+    /// ## Defaults
+    ///
+    /// Default to half of the cache size.
+    pub fn set_dirty_page_budget(&mut self, bytes: usize) -> &mut Self {
+        self.dirty_page_budget = Some(bytes);
+        self
+    }
+
+    /// Collect I/O statistics for each table, which are reported by [`Database::io_stats`]
+    ///
+    /// This adds a small overhead to every page read, so it is disabled by default. Statistics for
+    /// the whole database are always collected.
+    ///
+    /// ## Defaults
+    ///
+    /// Default to `false`.
+    pub fn set_table_io_stats(&mut self, enabled: bool) -> &mut Self {
+        self.table_io_stats = enabled;
+        self
+    }
+
+    /// Capture a backtrace when each read transaction begins, which is reported by
+    /// [`Database::live_read_transactions`]
+    ///
+    /// Capturing a backtrace is slow, so this is intended for tracking down read transactions
+    /// which are held open for too long.
+    ///
+    /// ## Defaults
+    ///
+    /// Default to `false`.
+    pub fn set_read_transaction_backtraces(&mut self, enabled: bool) -> &mut Self {
+        self.read_transaction_backtraces = enabled;
+        self
+    }
+
+    /// Set a callback which will be invoked once for each read transaction that is still live
+    /// `max_age` after it began
+    ///
+    /// Live read transactions prevent the pages freed by later write transactions from being
+    /// reused, which causes the database file to grow. The ages of live read transactions are
+    /// checked whenever a read or write transaction begins, and the callback is invoked on the
+    /// thread that is beginning the transaction.
+    ///
+    /// ## Defaults
+    ///
+    /// Default to no callback.
+    pub fn set_read_transaction_warning(
+        &mut self,
+        max_age: Duration,
+        callback: impl Fn(&ReadTransactionInfo) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.read_transaction_warning = Some((max_age, Arc::new(callback)));
+        self
+    }
+
+    /// Set the maximum age of read transactions
+    ///
+    /// Once a read transaction exceeds this age, its snapshot is released so that the pages freed
+    /// by later write transactions can be reused, and all further reads through the transaction,
+    /// or through the tables and iterators opened from it, return
+    /// [`StorageError::SnapshotExpired`]. Data which was already read remains valid.
+    ///
+    /// Like [`Self::set_read_transaction_warning`], ages are checked whenever a read or write
+    /// transaction begins, so a read transaction may outlive this age until then.
+    ///
+    /// ## Defaults
+    ///
+    /// Default to no limit.
+    pub fn set_max_read_transaction_age(&mut self, max_age: Duration) -> &mut Self {
+        self.max_read_transaction_age = Some(max_age);
+        self
+    }
+
     /// Opens the specified file as a redb database.
     /// * if the file does not exist, or is an empty file, a new database will be initialized in it
     /// * if the file is a valid redb database, it will be opened
@@ -1354,28 +1789,14 @@ impl Builder {
             .truncate(false)
             .open(path)?;
 
-        Database::new(
-            Box::new(FileBackend::new(file)?),
-            true,
-            self.page_size,
-            self.region_size,
-            self.cache_size,
-            &self.repair_callback,
-        )
+        Database::new(Box::new(FileBackend::new(file)?), true, self)
     }
 
     /// Opens an existing redb database.
     pub fn open(&self, path: impl AsRef<Path>) -> Result<Database, DatabaseError> {
         let file = OpenOptions::new().read(true).write(true).open(path)?;
 
-        Database::new(
-            Box::new(FileBackend::new(file)?),
-            false,
-            self.page_size,
-            None,
-            self.cache_size,
-            &self.repair_callback,
-        )
+        Database::new(Box::new(FileBackend::new(file)?), false, self)
     }
 
     /// Opens an existing redb database.
@@ -1389,26 +1810,14 @@ impl Builder {
     ) -> Result<ReadOnlyDatabase, DatabaseError> {
         let file = OpenOptions::new().read(true).open(path)?;
 
-        ReadOnlyDatabase::new(
-            Box::new(FileBackend::new_internal(file, true)?),
-            self.page_size,
-            None,
-            self.cache_size,
-        )
+        ReadOnlyDatabase::new(Box::new(FileBackend::new_internal(file, true)?), self)
     }
 
     /// Open an existing or create a new database in the given `file`.
     ///
     /// The file must be empty or contain a valid database.
     pub fn create_file(&self, file: File) -> Result<Database, DatabaseError> {
-        Database::new(
-            Box::new(FileBackend::new(file)?),
-            true,
-            self.page_size,
-            self.region_size,
-            self.cache_size,
-            &self.repair_callback,
-        )
+        Database::new(Box::new(FileBackend::new(file)?), true, self)
     }
 
     /// Open an existing or create a new database with the given backend.
@@ -1416,14 +1825,7 @@ impl Builder {
         &self,
         backend: impl StorageBackend,
     ) -> Result<Database, DatabaseError> {
-        Database::new(
-            Box::new(backend),
-            true,
-            self.page_size,
-            self.region_size,
-            self.cache_size,
-            &self.repair_callback,
-        )
+        Database::new(Box::new(backend), true, self)
     }
 }
 
@@ -1865,4 +2267,73 @@ mod test {
             err => panic!("Unexpected error for empty file: {err}"),
         }
     }
+
+    // Collects the output of a `tracing_subscriber::fmt` subscriber
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Default)]
+    struct TracingOutput(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    #[cfg(feature = "tracing")]
+    impl std::io::Write for TracingOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tracing_spans() {
+        let tmpfile = crate::create_tempfile();
+        let output = TracingOutput::default();
+        let writer = output.clone();
+        // Log every span when it closes, along with the fields recorded on it
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+            .with_writer(move || writer.clone())
+            .finish();
+        let table_def: TableDefinition<u64, u64> = TableDefinition::new("x");
+
+        tracing::subscriber::with_default(subscriber, || {
+            let mut db = Database::create(tmpfile.path()).unwrap();
+            let tx = db.begin_write().unwrap();
+            {
+                let mut table = tx.open_table(table_def).unwrap();
+                table.insert(0, 0).unwrap();
+                table.remove(0).unwrap();
+            }
+            tx.commit().unwrap();
+            db.compact().unwrap();
+        });
+
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        for expected in [
+            "begin_write{transaction_id=",
+            "commit{transaction_id=",
+            "durability=",
+            "pages_written=",
+            "bytes_written=",
+            "finalize_checksums",
+            "flush{pages=",
+            "fsync",
+            "compact",
+        ] {
+            assert!(
+                output.contains(expected),
+                "missing {expected} in:\n{output}"
+            );
+        }
+        // Pending frees are drained by commit, as well as by compaction
+        assert!(
+            output
+                .lines()
+                .any(|line| line.contains("commit{") && line.contains(":drain_pending_free_pages")),
+            "{output}"
+        );
+    }
 }
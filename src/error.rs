@@ -12,6 +12,12 @@ pub enum StorageError {
     Corrupted(String),
     /// The value being inserted exceeds the maximum of 3GiB
     ValueTooLarge(usize),
+    /// The write would grow the database file past the limit set with
+    /// [`crate::Builder::set_max_file_size`]
+    FileSizeLimitExceeded(u64),
+    /// The read transaction was live for longer than the limit set with
+    /// [`crate::Builder::set_max_read_transaction_age`], and its snapshot has been released
+    SnapshotExpired,
     Io(io::Error),
     PreviousIo,
     DatabaseClosed,
@@ -35,6 +41,8 @@ impl From<StorageError> for Error {
         match err {
             StorageError::Corrupted(msg) => Error::Corrupted(msg),
             StorageError::ValueTooLarge(x) => Error::ValueTooLarge(x),
+            StorageError::FileSizeLimitExceeded(x) => Error::FileSizeLimitExceeded(x),
+            StorageError::SnapshotExpired => Error::SnapshotExpired,
             StorageError::Io(x) => Error::Io(x),
             StorageError::PreviousIo => Error::PreviousIo,
             StorageError::DatabaseClosed => Error::DatabaseClosed,
@@ -56,6 +64,15 @@ impl Display for StorageError {
                     MAX_VALUE_LENGTH / 1024 / 1024 / 1024
                 )
             }
+            StorageError::FileSizeLimitExceeded(limit) => {
+                write!(
+                    f,
+                    "Database file size would exceed the limit of {limit} bytes"
+                )
+            }
+            StorageError::SnapshotExpired => {
+                write!(f, "Read transaction exceeded the maximum age")
+            }
             StorageError::Io(err) => {
                 write!(f, "I/O error: {err}")
             }
@@ -540,6 +557,12 @@ pub enum Error {
     UpgradeRequired(u8),
     /// The value being inserted exceeds the maximum of 3GiB
     ValueTooLarge(usize),
+    /// The write would grow the database file past the limit set with
+    /// [`crate::Builder::set_max_file_size`]
+    FileSizeLimitExceeded(u64),
+    /// The read transaction was live for longer than the limit set with
+    /// [`crate::Builder::set_max_read_transaction_age`], and its snapshot has been released
+    SnapshotExpired,
     /// Table types didn't match.
     TableTypeMismatch {
         table: String,
@@ -602,6 +625,15 @@ impl Display for Error {
                     MAX_VALUE_LENGTH / 1024 / 1024 / 1024
                 )
             }
+            Error::FileSizeLimitExceeded(limit) => {
+                write!(
+                    f,
+                    "Database file size would exceed the limit of {limit} bytes"
+                )
+            }
+            Error::SnapshotExpired => {
+                write!(f, "Read transaction exceeded the maximum age")
+            }
             Error::TypeDefinitionChanged {
                 name,
                 alignment,
@@ -69,17 +69,39 @@ pub use error::{
     CommitError, CompactionError, DatabaseError, Error, SavepointError, SetDurabilityError,
     StorageError, TableError, TransactionError,
 };
+pub use memcomparable::{Memcomparable, MemcomparableEncoding};
 pub use multimap_table::{
     MultimapRange, MultimapTable, MultimapValue, ReadOnlyMultimapTable,
     ReadOnlyUntypedMultimapTable, ReadableMultimapTable,
 };
 pub use table::{
-    Entry, ExtractIf, OccupiedEntry, Range, ReadOnlyTable, ReadOnlyUntypedTable, ReadableTable,
-    ReadableTableMetadata, Table, TableStats, VacantEntry,
+    Entry, ExtractIf, OccupiedEntry, Range, ReadOnlyTable, ReadOnlyUntypedTable, ReadOptions,
+    ReadableTable, ReadableTableMetadata, Table, TableStats, VacantEntry,
 };
-pub use transactions::{DatabaseStats, Durability, ReadTransaction, WriteTransaction};
-pub use tree_store::{AccessGuard, AccessGuardMut, AccessGuardMutInPlace, Savepoint};
-pub use types::{Key, MutInPlaceValue, TypeName, Value};
+pub use transactions::{
+    DatabaseStats, Durability, ReadTransaction, ReadTransactionInfo, WriteTransaction,
+};
+pub use tree_store::{
+    AccessGuard, AccessGuardMut, AccessGuardMutInPlace, CachePolicy, Histogram, IoStats,
+    RegionSpaceStats, Savepoint, SpaceStats, TableIoStats,
+};
+#[cfg(feature = "bytemuck")]
+pub use types::bytemuck::PodCopyValue;
+#[cfg(feature = "rkyv")]
+pub use types::rkyv::{RkyvArchive, RkyvValue};
+#[cfg(feature = "serde_bincode")]
+pub use types::serde::BincodeCodec;
+#[cfg(feature = "serde_cbor")]
+pub use types::serde::CborCodec;
+#[cfg(feature = "serde_json")]
+pub use types::serde::JsonCodec;
+#[cfg(feature = "serde_postcard")]
+pub use types::serde::PostcardCodec;
+#[cfg(feature = "serde_storekey")]
+pub use types::serde::StorekeyCodec;
+#[cfg(feature = "serde")]
+pub use types::serde::{OrderPreservingCodec, SerdeCodec, SerdeKey, SerdeValue};
+pub use types::{Key, MutInPlaceValue, StableTypeName, TypeName, Value};
 
 pub type Result<T = (), E = StorageError> = std::result::Result<T, E>;
 
@@ -87,6 +109,7 @@ pub mod backends;
 mod complex_types;
 mod db;
 mod error;
+mod memcomparable;
 mod multimap_table;
 mod sealed;
 mod table;
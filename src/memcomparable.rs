@@ -0,0 +1,350 @@
+// Big-endian encodings are what make integers order-preserving
+#![allow(clippy::big_endian_bytes)]
+
+use crate::types::{Key, TypeName, Value};
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::mem::size_of;
+
+/// A type which has an order-preserving ("memcomparable") binary encoding, in which comparing the
+/// encodings of two values byte-wise gives the same result as comparing the values themselves
+///
+/// See [`Memcomparable`]
+pub trait MemcomparableEncoding: Value + Sized {
+    /// Width of the encoding, if it is the same for every value
+    fn encoded_width() -> Option<usize>;
+
+    /// Appends the encoding of this value to `output`
+    fn encode(&self, output: &mut Vec<u8>);
+
+    /// Decodes a value from the start of `data`, and returns it along with the number of bytes
+    /// consumed
+    fn decode(data: &[u8]) -> (Self, usize);
+}
+
+/// A key type which stores `T` in its memcomparable encoding, so that keys are compared with a
+/// plain byte-wise comparison, and the keys of a table are sorted by their raw bytes
+///
+/// Values of `T` are used directly as keys. For example, a table defined as
+/// `TableDefinition<Memcomparable<(u64, String)>, V>` has keys of type `(u64, String)`.
+///
+/// The encoding is:
+/// * unsigned integers: big-endian
+/// * signed integers: big-endian, with the sign bit inverted
+/// * floats: big-endian, with the sign bit inverted for positive values and all bits inverted
+///   for negative values, so that they sort in the same order as `total_cmp()`
+/// * `String` and `Vec<u8>`: the bytes, with each `0x00` escaped as `0x00 0xFF`, followed by
+///   `0x00 0x00`
+/// * `Option<T>`: `0x00` followed by zero padding, if `T` is fixed width, for `None`, and `0x01`
+///   followed by the encoding of `T` for `Some`
+/// * tuples and arrays: the concatenation of the encodings of their elements
+///
+/// Since the encoding differs from that of `T`, a table of `Memcomparable<T>` keys is not
+/// compatible with a table of `T` keys
+#[derive(Debug)]
+pub struct Memcomparable<T>(PhantomData<T>);
+
+impl<T: MemcomparableEncoding + 'static> Value for Memcomparable<T> {
+    type SelfType<'a>
+        = T
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        T::encoded_width()
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> T
+    where
+        Self: 'a,
+    {
+        let (value, len) = T::decode(data);
+        assert_eq!(len, data.len());
+        value
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a T) -> Vec<u8>
+    where
+        Self: 'b,
+    {
+        let mut result = Vec::with_capacity(T::encoded_width().unwrap_or(16));
+        value.encode(&mut result);
+        result
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::internal(&format!("Memcomparable<{}>", T::type_name().name()))
+    }
+}
+
+impl<T: MemcomparableEncoding + 'static> Key for Memcomparable<T> {
+    fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+        data1.cmp(data2)
+    }
+}
+
+macro_rules! unsigned_impl {
+    ($t:ty) => {
+        impl MemcomparableEncoding for $t {
+            fn encoded_width() -> Option<usize> {
+                Some(size_of::<$t>())
+            }
+
+            fn encode(&self, output: &mut Vec<u8>) {
+                output.extend_from_slice(&self.to_be_bytes());
+            }
+
+            fn decode(data: &[u8]) -> (Self, usize) {
+                let width = size_of::<$t>();
+                (
+                    <$t>::from_be_bytes(data[..width].try_into().unwrap()),
+                    width,
+                )
+            }
+        }
+    };
+}
+
+macro_rules! signed_impl {
+    ($t:ty, $u:ty) => {
+        impl MemcomparableEncoding for $t {
+            fn encoded_width() -> Option<usize> {
+                Some(size_of::<$t>())
+            }
+
+            fn encode(&self, output: &mut Vec<u8>) {
+                let flipped = <$u>::from_be_bytes(self.to_be_bytes()) ^ (1 << (<$u>::BITS - 1));
+                output.extend_from_slice(&flipped.to_be_bytes());
+            }
+
+            fn decode(data: &[u8]) -> (Self, usize) {
+                let width = size_of::<$t>();
+                let flipped = <$u>::from_be_bytes(data[..width].try_into().unwrap());
+                let value = flipped ^ (1 << (<$u>::BITS - 1));
+                (<$t>::from_be_bytes(value.to_be_bytes()), width)
+            }
+        }
+    };
+}
+
+macro_rules! float_impl {
+    ($t:ty, $u:ty) => {
+        impl MemcomparableEncoding for $t {
+            fn encoded_width() -> Option<usize> {
+                Some(size_of::<$t>())
+            }
+
+            fn encode(&self, output: &mut Vec<u8>) {
+                let bits = self.to_bits();
+                let sign = 1 << (<$u>::BITS - 1);
+                let flipped = if bits & sign == 0 { bits ^ sign } else { !bits };
+                output.extend_from_slice(&flipped.to_be_bytes());
+            }
+
+            fn decode(data: &[u8]) -> (Self, usize) {
+                let width = size_of::<$t>();
+                let flipped = <$u>::from_be_bytes(data[..width].try_into().unwrap());
+                let sign = 1 << (<$u>::BITS - 1);
+                let bits = if flipped & sign == 0 {
+                    !flipped
+                } else {
+                    flipped ^ sign
+                };
+                (<$t>::from_bits(bits), width)
+            }
+        }
+    };
+}
+
+unsigned_impl!(u8);
+unsigned_impl!(u16);
+unsigned_impl!(u32);
+unsigned_impl!(u64);
+unsigned_impl!(u128);
+signed_impl!(i8, u8);
+signed_impl!(i16, u16);
+signed_impl!(i32, u32);
+signed_impl!(i64, u64);
+signed_impl!(i128, u128);
+float_impl!(f32, u32);
+float_impl!(f64, u64);
+
+impl MemcomparableEncoding for bool {
+    fn encoded_width() -> Option<usize> {
+        Some(1)
+    }
+
+    fn encode(&self, output: &mut Vec<u8>) {
+        output.push(u8::from(*self));
+    }
+
+    fn decode(data: &[u8]) -> (Self, usize) {
+        (data[0] != 0, 1)
+    }
+}
+
+impl MemcomparableEncoding for () {
+    fn encoded_width() -> Option<usize> {
+        Some(0)
+    }
+
+    fn encode(&self, _output: &mut Vec<u8>) {}
+
+    fn decode(_data: &[u8]) -> (Self, usize) {
+        ((), 0)
+    }
+}
+
+// Escapes each zero byte as 0x00 0xFF, and terminates with 0x00 0x00, so that a string sorts
+// before any longer string that it is a prefix of
+fn encode_bytes(data: &[u8], output: &mut Vec<u8>) {
+    for &byte in data {
+        output.push(byte);
+        if byte == 0 {
+            output.push(0xFF);
+        }
+    }
+    output.extend_from_slice(&[0, 0]);
+}
+
+fn decode_bytes(data: &[u8]) -> (Vec<u8>, usize) {
+    let mut result = vec![];
+    let mut offset = 0;
+    loop {
+        let end = offset + data[offset..].iter().position(|x| *x == 0).unwrap();
+        result.extend_from_slice(&data[offset..end]);
+        if data[end + 1] == 0 {
+            return (result, end + 2);
+        }
+        result.push(0);
+        offset = end + 2;
+    }
+}
+
+impl MemcomparableEncoding for Vec<u8> {
+    fn encoded_width() -> Option<usize> {
+        None
+    }
+
+    fn encode(&self, output: &mut Vec<u8>) {
+        encode_bytes(self, output);
+    }
+
+    fn decode(data: &[u8]) -> (Self, usize) {
+        decode_bytes(data)
+    }
+}
+
+impl MemcomparableEncoding for String {
+    fn encoded_width() -> Option<usize> {
+        None
+    }
+
+    fn encode(&self, output: &mut Vec<u8>) {
+        encode_bytes(self.as_bytes(), output);
+    }
+
+    fn decode(data: &[u8]) -> (Self, usize) {
+        let (bytes, len) = decode_bytes(data);
+        (String::from_utf8(bytes).unwrap(), len)
+    }
+}
+
+impl<T: MemcomparableEncoding> MemcomparableEncoding for Option<T> {
+    fn encoded_width() -> Option<usize> {
+        T::encoded_width().map(|x| x + 1)
+    }
+
+    fn encode(&self, output: &mut Vec<u8>) {
+        if let Some(value) = self {
+            output.push(1);
+            value.encode(output);
+        } else {
+            output.push(0);
+            if let Some(width) = T::encoded_width() {
+                output.resize(output.len() + width, 0);
+            }
+        }
+    }
+
+    fn decode(data: &[u8]) -> (Self, usize) {
+        if data[0] == 0 {
+            (None, 1 + T::encoded_width().unwrap_or(0))
+        } else {
+            let (value, len) = T::decode(&data[1..]);
+            (Some(value), len + 1)
+        }
+    }
+}
+
+impl<T: MemcomparableEncoding, const N: usize> MemcomparableEncoding for [T; N] {
+    fn encoded_width() -> Option<usize> {
+        T::encoded_width().map(|x| x * N)
+    }
+
+    fn encode(&self, output: &mut Vec<u8>) {
+        for value in self {
+            value.encode(output);
+        }
+    }
+
+    fn decode(data: &[u8]) -> (Self, usize) {
+        let mut offset = 0;
+        let result = std::array::from_fn(|_| {
+            let (value, len) = T::decode(&data[offset..]);
+            offset += len;
+            value
+        });
+        (result, offset)
+    }
+}
+
+macro_rules! tuple_impl {
+    ( $($t:ident, $i:tt),+ ) => {
+        impl<$($t: MemcomparableEncoding),+> MemcomparableEncoding for ($($t,)+) {
+            fn encoded_width() -> Option<usize> {
+                Some(0 $(+ $t::encoded_width()?)+)
+            }
+
+            fn encode(&self, output: &mut Vec<u8>) {
+                $(self.$i.encode(output);)+
+            }
+
+            fn decode(data: &[u8]) -> (Self, usize) {
+                let mut offset = 0;
+                let result = ($({
+                    let (value, len) = $t::decode(&data[offset..]);
+                    offset += len;
+                    value
+                },)+);
+                (result, offset)
+            }
+        }
+    };
+}
+
+tuple_impl!(T0, 0);
+tuple_impl!(T0, 0, T1, 1);
+tuple_impl!(T0, 0, T1, 1, T2, 2);
+tuple_impl!(T0, 0, T1, 1, T2, 2, T3, 3);
+tuple_impl!(T0, 0, T1, 1, T2, 2, T3, 3, T4, 4);
+tuple_impl!(T0, 0, T1, 1, T2, 2, T3, 3, T4, 4, T5, 5);
+tuple_impl!(T0, 0, T1, 1, T2, 2, T3, 3, T4, 4, T5, 5, T6, 6);
+tuple_impl!(T0, 0, T1, 1, T2, 2, T3, 3, T4, 4, T5, 5, T6, 6, T7, 7);
+tuple_impl!(
+    T0, 0, T1, 1, T2, 2, T3, 3, T4, 4, T5, 5, T6, 6, T7, 7, T8, 8
+);
+tuple_impl!(
+    T0, 0, T1, 1, T2, 2, T3, 3, T4, 4, T5, 5, T6, 6, T7, 7, T8, 8, T9, 9
+);
+tuple_impl!(
+    T0, 0, T1, 1, T2, 2, T3, 3, T4, 4, T5, 5, T6, 6, T7, 7, T8, 8, T9, 9, T10, 10
+);
+tuple_impl!(
+    T0, 0, T1, 1, T2, 2, T3, 3, T4, 4, T5, 5, T6, 6, T7, 7, T8, 8, T9, 9, T10, 10, T11, 11
+);
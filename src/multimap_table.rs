@@ -5,8 +5,8 @@ use crate::table::{ReadableTableMetadata, TableStats};
 use crate::tree_store::{
     AllPageNumbersBtreeIter, BRANCH, Btree, BtreeCursorRange, BtreeHeader, BtreeMut,
     DynamicCollection, DynamicCollectionType, LEAF, LeafAccessor, MAX_PAIR_LENGTH,
-    MAX_VALUE_LENGTH, Page, PageAllocator, PageHint, PageNumber, PageResolver, PageTrackerPolicy,
-    RawBtree, RawLeafBuilder, multimap_btree_stats,
+    MAX_VALUE_LENGTH, MODIFICATION_PAGES, Page, PageAllocator, PageHint, PageNumber, PageResolver,
+    PageTrackerPolicy, RawBtree, RawLeafBuilder, multimap_btree_stats,
 };
 use crate::types::{Key, Value};
 use crate::{AccessGuard, MultimapTableHandle, Result, StorageError, WriteTransaction};
@@ -459,7 +459,17 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
         if value_bytes_ref.len() + key_len > MAX_PAIR_LENGTH {
             return Err(StorageError::ValueTooLarge(value_bytes_ref.len() + key_len));
         }
-        let get_result = self.tree.get(key.borrow())?;
+        // Both the value collection and the outer tree may need to be modified
+        self.page_allocator
+            .ensure_capacity(|| value_bytes_ref.len() + key_len, 4 * MODIFICATION_PAGES)?;
+        let result = self.insert_helper(key.borrow(), value.borrow());
+        self.page_allocator.check_limit(result)
+    }
+
+    fn insert_helper(&mut self, key: &K::SelfType<'_>, value: &V::SelfType<'_>) -> Result<bool> {
+        let value_bytes = V::as_bytes(value);
+        let value_bytes_ref = value_bytes.as_ref();
+        let get_result = self.tree.get(key)?;
         let existed = if get_result.is_some() {
             #[allow(clippy::unnecessary_unwrap)]
             let guard = get_result.unwrap();
@@ -519,7 +529,7 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
                         drop(guard);
                         let inline_data = DynamicCollection::<V>::make_inline_data(&data);
                         self.tree
-                            .insert(key.borrow(), &DynamicCollection::new(&inline_data))?;
+                            .insert(key, &DynamicCollection::new(&inline_data))?;
                     } else {
                         // convert into a subtree
                         let mut allocated = self.allocated_pages.lock().unwrap();
@@ -540,12 +550,12 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
                             self.freed_pages.clone(),
                             self.allocated_pages.clone(),
                         );
-                        let existed = subtree.insert(value.borrow(), &())?.is_some();
+                        let existed = subtree.insert(value, &())?.is_some();
                         assert_eq!(existed, found);
                         let subtree_data =
                             DynamicCollection::<V>::make_subtree_data(subtree.get_root().unwrap());
                         self.tree
-                            .insert(key.borrow(), &DynamicCollection::new(&subtree_data))?;
+                            .insert(key, &DynamicCollection::new(&subtree_data))?;
                     }
 
                     found
@@ -559,11 +569,11 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
                         self.allocated_pages.clone(),
                     );
                     drop(guard);
-                    let existed = subtree.insert(value.borrow(), &())?.is_some();
+                    let existed = subtree.insert(value, &())?.is_some();
                     let subtree_data =
                         DynamicCollection::<V>::make_subtree_data(subtree.get_root().unwrap());
                     self.tree
-                        .insert(key.borrow(), &DynamicCollection::new(&subtree_data))?;
+                        .insert(key, &DynamicCollection::new(&subtree_data))?;
 
                     existed
                 }
@@ -589,7 +599,7 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
                 drop(builder);
                 let inline_data = DynamicCollection::<V>::make_inline_data(&data);
                 self.tree
-                    .insert(key.borrow(), &DynamicCollection::new(&inline_data))?;
+                    .insert(key, &DynamicCollection::new(&inline_data))?;
             } else {
                 let mut subtree: BtreeMut<V, ()> = BtreeMut::new(
                     None,
@@ -598,11 +608,11 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
                     self.freed_pages.clone(),
                     self.allocated_pages.clone(),
                 );
-                subtree.insert(value.borrow(), &())?;
+                subtree.insert(value, &())?;
                 let subtree_data =
                     DynamicCollection::<V>::make_subtree_data(subtree.get_root().unwrap());
                 self.tree
-                    .insert(key.borrow(), &DynamicCollection::new(&subtree_data))?;
+                    .insert(key, &DynamicCollection::new(&subtree_data))?;
             }
             false
         };
@@ -622,7 +632,15 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
         key: impl Borrow<K::SelfType<'k>>,
         value: impl Borrow<V::SelfType<'v>>,
     ) -> Result<bool> {
-        let get_result = self.tree.get(key.borrow())?;
+        // Both the value collection and the outer tree may need to be modified
+        self.page_allocator
+            .ensure_capacity(|| 0, 4 * MODIFICATION_PAGES)?;
+        let result = self.remove_helper(key.borrow(), value.borrow());
+        self.page_allocator.check_limit(result)
+    }
+
+    fn remove_helper(&mut self, key: &K::SelfType<'_>, value: &V::SelfType<'_>) -> Result<bool> {
+        let get_result = self.tree.get(key)?;
         if get_result.is_none() {
             return Ok(false);
         }
@@ -633,12 +651,11 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
                 let leaf_data = v.as_inline();
                 let accessor =
                     LeafAccessor::new(leaf_data, V::fixed_width(), <() as Value>::fixed_width());
-                if let Some(position) = accessor.find_key::<V>(V::as_bytes(value.borrow()).as_ref())
-                {
+                if let Some(position) = accessor.find_key::<V>(V::as_bytes(value).as_ref()) {
                     let old_num_pairs = accessor.num_pairs();
                     if old_num_pairs == 1 {
                         drop(guard);
-                        self.tree.remove(key.borrow())?;
+                        self.tree.remove(key)?;
                     } else {
                         let old_pairs_len = accessor.length_of_pairs(0, old_num_pairs);
                         let removed_value_len = accessor.entry(position).unwrap().key().len();
@@ -669,7 +686,7 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
 
                         let inline_data = DynamicCollection::<V>::make_inline_data(&new_data);
                         self.tree
-                            .insert(key.borrow(), &DynamicCollection::new(&inline_data))?;
+                            .insert(key, &DynamicCollection::new(&inline_data))?;
                     }
                     true
                 } else {
@@ -686,7 +703,7 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
                     self.allocated_pages.clone(),
                 );
                 drop(guard);
-                let existed = subtree.remove(value.borrow())?.is_some();
+                let existed = subtree.remove(value)?.is_some();
 
                 if let Some(BtreeHeader {
                     root: new_root,
@@ -707,7 +724,7 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
                                 let inline_data =
                                     DynamicCollection::<V>::make_inline_data(&page.memory()[..len]);
                                 self.tree
-                                    .insert(key.borrow(), &DynamicCollection::new(&inline_data))?;
+                                    .insert(key, &DynamicCollection::new(&inline_data))?;
                                 drop(page);
                                 let mut allocated_pages = self.allocated_pages.lock().unwrap();
                                 if !self
@@ -724,7 +741,7 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
                                         accessor.num_pairs() as u64,
                                     ));
                                 self.tree
-                                    .insert(key.borrow(), &DynamicCollection::new(&subtree_data))?;
+                                    .insert(key, &DynamicCollection::new(&subtree_data))?;
                             }
                         }
                         BRANCH => {
@@ -732,12 +749,12 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
                                 BtreeHeader::new(new_root, new_checksum, new_length),
                             );
                             self.tree
-                                .insert(key.borrow(), &DynamicCollection::new(&subtree_data))?;
+                                .insert(key, &DynamicCollection::new(&subtree_data))?;
                         }
                         _ => unreachable!(),
                     }
                 } else {
-                    self.tree.remove(key.borrow())?;
+                    self.tree.remove(key)?;
                 }
 
                 existed
@@ -959,6 +976,14 @@ impl ReadOnlyUntypedMultimapTable {
             mem,
         }
     }
+
+    pub(crate) fn prefetch_branches(&self, budget: &mut u64) -> Result<Vec<PageNumber>> {
+        self.tree.prefetch_branches(budget)
+    }
+
+    pub(crate) fn prefetch_pages(&self, pages: &[PageNumber], budget: &mut u64) -> Result {
+        self.tree.prefetch_pages(pages, budget)
+    }
 }
 
 /// A read-only multimap table
@@ -15,6 +15,41 @@ use std::ops::RangeBounds;
 use std::sync::{Arc, Mutex};
 use std::thread;
 
+/// Options for reading from a table
+///
+/// See [`ReadableTable::range_with`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ReadOptions {
+    fill_cache: bool,
+}
+
+impl ReadOptions {
+    /// Returns the default options
+    pub fn new() -> Self {
+        Self { fill_cache: true }
+    }
+
+    /// Set whether pages that are not already in the cache are inserted into it
+    ///
+    /// Passing `false` is useful for large scans, such as exports, to avoid evicting the pages
+    /// that other reads depend on.
+    ///
+    /// ## Defaults
+    ///
+    /// Default to `true`.
+    #[must_use]
+    pub fn fill_cache(mut self, fill_cache: bool) -> Self {
+        self.fill_cache = fill_cache;
+        self
+    }
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Informational storage stats about a table
 #[derive(Debug)]
 pub struct TableStats {
@@ -341,6 +376,19 @@ impl<K: Key + 'static, V: Value + 'static> ReadableTable<K, V> for Table<'_, K,
             .map(|x| Range::new(x, self.transaction.transaction_guard()))
     }
 
+    fn range_with<'a, KR>(
+        &self,
+        range: impl RangeBounds<KR> + 'a,
+        options: ReadOptions,
+    ) -> Result<Range<'_, K, V>>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        self.tree
+            .range_with(&range, options.fill_cache)
+            .map(|x| Range::new(x, self.transaction.transaction_guard()))
+    }
+
     fn first(&self) -> Result<Option<(AccessGuard<'_, K>, AccessGuard<'_, V>)>> {
         self.tree.first()
     }
@@ -467,6 +515,56 @@ pub trait ReadableTable<K: Key + 'static, V: Value + 'static>: ReadableTableMeta
     where
         KR: Borrow<K::SelfType<'a>> + 'a;
 
+    /// Like [`ReadableTable::range()`], but with the given [`ReadOptions`]
+    ///
+    /// The default implementation ignores `options`, and is overridden by redb's tables
+    ///
+    /// # Examples
+    ///
+    /// Scan a table without evicting other pages from the cache:
+    /// ```rust
+    /// use redb::*;
+    /// # use tempfile::NamedTempFile;
+    /// const TABLE: TableDefinition<u64, u64> = TableDefinition::new("my_data");
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// # #[cfg(not(target_os = "wasi"))]
+    /// # let tmpfile = NamedTempFile::new().unwrap();
+    /// # #[cfg(target_os = "wasi")]
+    /// # let tmpfile = NamedTempFile::new_in("/tmp").unwrap();
+    /// # let filename = tmpfile.path();
+    /// let db = Database::create(filename)?;
+    /// let write_txn = db.begin_write()?;
+    /// {
+    ///     let mut table = write_txn.open_table(TABLE)?;
+    ///     for i in 0..100 {
+    ///         table.insert(i, i)?;
+    ///     }
+    /// }
+    /// write_txn.commit()?;
+    ///
+    /// let read_txn = db.begin_read()?;
+    /// let table = read_txn.open_table(TABLE)?;
+    /// let mut sum = 0;
+    /// for entry in table.range_with::<u64>(.., ReadOptions::new().fill_cache(false))? {
+    ///     sum += entry?.1.value();
+    /// }
+    /// assert_eq!(sum, 4950);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn range_with<'a, KR>(
+        &self,
+        range: impl RangeBounds<KR> + 'a,
+        options: ReadOptions,
+    ) -> Result<Range<'_, K, V>>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        let _ = options;
+        self.range(range)
+    }
+
     /// Returns the first key-value pair in the table, if it exists
     fn first(&self) -> Result<Option<(AccessGuard<'_, K>, AccessGuard<'_, V>)>>;
 
@@ -527,6 +625,14 @@ impl ReadOnlyUntypedTable {
             tree: RawBtree::new(root_page, fixed_key_size, fixed_value_size, mem, hint),
         }
     }
+
+    pub(crate) fn prefetch_branches(&self, budget: &mut u64) -> Result<Vec<PageNumber>> {
+        self.tree.prefetch_branches(budget)
+    }
+
+    pub(crate) fn prefetch_pages(&self, pages: &[PageNumber], budget: &mut u64) -> Result {
+        self.tree.prefetch_pages(pages, budget)
+    }
 }
 
 /// A read-only table
@@ -611,6 +717,19 @@ impl<K: Key + 'static, V: Value + 'static> ReadableTable<K, V> for ReadOnlyTable
             .map(|x| Range::new(x, self.transaction_guard.clone()))
     }
 
+    fn range_with<'a, KR>(
+        &self,
+        range: impl RangeBounds<KR> + 'a,
+        options: ReadOptions,
+    ) -> Result<Range<'_, K, V>>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        self.tree
+            .range_with(&range, options.fill_cache)
+            .map(|x| Range::new(x, self.transaction_guard.clone()))
+    }
+
     fn first(&self) -> Result<Option<(AccessGuard<'_, K>, AccessGuard<'_, V>)>> {
         self.tree.first()
     }
@@ -1,13 +1,16 @@
-use crate::tree_store::TransactionalMemory;
-use crate::{Key, Result, Savepoint, TypeName, Value};
+use crate::tree_store::{Stopwatch, TransactionalMemory};
+use crate::{Key, ReadTransactionInfo, Result, Savepoint, TypeName, Value};
 #[cfg(feature = "logging")]
 use log::debug;
+use std::backtrace::Backtrace;
 use std::cmp::Ordering;
 use std::collections::btree_map::BTreeMap;
 use std::collections::{BTreeSet, HashMap};
 use std::mem;
 use std::mem::size_of;
-use std::sync::{Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
 
 #[derive(Copy, Clone, Hash, Ord, PartialOrd, Eq, PartialEq, Debug)]
 pub(crate) struct TransactionId(u64);
@@ -74,6 +77,33 @@ impl Key for SavepointId {
     }
 }
 
+// Callback invoked for read transactions which are still live after the given age
+pub(crate) type ReadTransactionWarning =
+    (Duration, Arc<dyn Fn(&ReadTransactionInfo) + Send + Sync>);
+
+// A read transaction begun by the user, as opposed to the internal references held by savepoints
+// and non-durable commits
+struct UserReadTransaction {
+    transaction_id: TransactionId,
+    started: Stopwatch,
+    backtrace: Option<Arc<Backtrace>>,
+    // Whether the age warning callback has already been invoked for this transaction
+    warned: bool,
+    // Set once the transaction exceeds the maximum age. Its reference in live_read_transactions
+    // is released at the same time
+    expired: Arc<AtomicBool>,
+}
+
+impl UserReadTransaction {
+    fn info(&self) -> ReadTransactionInfo {
+        ReadTransactionInfo {
+            transaction_id: self.transaction_id.raw_id(),
+            age: self.started.elapsed(),
+            backtrace: self.backtrace.clone(),
+        }
+    }
+}
+
 struct State {
     next_savepoint_id: SavepointId,
     // reference count of read transactions per transaction id
@@ -91,11 +121,28 @@ struct State {
     pending_non_durable_commits: HashMap<TransactionId, TransactionId>,
     // Non-durable commits which have NOT been processed in the freed table
     unprocessed_freed_non_durable_commits: BTreeSet<TransactionId>,
+    // Keyed by a handle which is unique for the lifetime of the tracker. Each of these also holds
+    // a reference in live_read_transactions
+    user_read_transactions: HashMap<u64, UserReadTransaction>,
+    next_user_read_handle: u64,
+}
+
+impl State {
+    fn release_read_reference(&mut self, id: TransactionId) {
+        let ref_count = self.live_read_transactions.get_mut(&id).unwrap();
+        *ref_count -= 1;
+        if *ref_count == 0 {
+            self.live_read_transactions.remove(&id);
+        }
+    }
 }
 
 pub(crate) struct TransactionTracker {
     state: Mutex<State>,
     live_write_transaction_available: Condvar,
+    read_transaction_backtraces: bool,
+    read_transaction_warning: Option<ReadTransactionWarning>,
+    max_read_transaction_age: Option<Duration>,
 }
 
 impl TransactionTracker {
@@ -110,11 +157,28 @@ impl TransactionTracker {
                 persistent_savepoints: BTreeSet::default(),
                 pending_non_durable_commits: HashMap::default(),
                 unprocessed_freed_non_durable_commits: BTreeSet::default(),
+                user_read_transactions: HashMap::default(),
+                next_user_read_handle: 0,
             }),
             live_write_transaction_available: Condvar::new(),
+            read_transaction_backtraces: false,
+            read_transaction_warning: None,
+            max_read_transaction_age: None,
         }
     }
 
+    pub(crate) fn with_read_transaction_monitoring(
+        mut self,
+        backtraces: bool,
+        warning: Option<ReadTransactionWarning>,
+        max_age: Option<Duration>,
+    ) -> Self {
+        self.read_transaction_backtraces = backtraces;
+        self.read_transaction_warning = warning;
+        self.max_read_transaction_age = max_age;
+        self
+    }
+
     pub(crate) fn start_write_transaction(&self) -> TransactionId {
         let mut state = self.state.lock().unwrap();
         while state.live_write_transaction.is_some() {
@@ -256,15 +320,106 @@ impl TransactionTracker {
         Ok(id)
     }
 
-    pub(crate) fn deallocate_read_transaction(&self, id: TransactionId) {
+    // Registers a read transaction begun by the user. Returns its snapshot id, the handle which
+    // must be passed to `end_user_read_transaction()`, and a flag which is set if the transaction
+    // expires
+    pub(crate) fn begin_user_read_transaction(
+        &self,
+        mem: &TransactionalMemory,
+    ) -> Result<(TransactionId, u64, Arc<AtomicBool>)> {
+        let backtrace = self
+            .read_transaction_backtraces
+            .then(|| Arc::new(Backtrace::force_capture()));
+        let id = self.register_read_transaction(mem)?;
         let mut state = self.state.lock().unwrap();
-        let ref_count = state.live_read_transactions.get_mut(&id).unwrap();
-        *ref_count -= 1;
-        if *ref_count == 0 {
-            state.live_read_transactions.remove(&id);
+        let handle = state.next_user_read_handle;
+        state.next_user_read_handle += 1;
+        let expired = Arc::new(AtomicBool::new(false));
+        state.user_read_transactions.insert(
+            handle,
+            UserReadTransaction {
+                transaction_id: id,
+                started: Stopwatch::start(),
+                backtrace,
+                warned: false,
+                expired: expired.clone(),
+            },
+        );
+
+        Ok((id, handle, expired))
+    }
+
+    pub(crate) fn end_user_read_transaction(&self, id: TransactionId, handle: u64) {
+        let mut state = self.state.lock().unwrap();
+        let removed = state.user_read_transactions.remove(&handle).unwrap();
+        debug_assert_eq!(removed.transaction_id, id);
+        // An expired transaction has already released its reference
+        if !removed.expired.load(AtomicOrdering::Acquire) {
+            state.release_read_reference(id);
         }
     }
 
+    // Oldest first
+    pub(crate) fn live_read_transactions(&self) -> Vec<ReadTransactionInfo> {
+        let state = self.state.lock().unwrap();
+        let mut result: Vec<(u64, ReadTransactionInfo)> = state
+            .user_read_transactions
+            .iter()
+            .filter(|(_, transaction)| !transaction.expired.load(AtomicOrdering::Acquire))
+            .map(|(handle, transaction)| (*handle, transaction.info()))
+            .collect();
+        result.sort_by_key(|(handle, _)| *handle);
+        result.into_iter().map(|(_, info)| info).collect()
+    }
+
+    // Invokes the warning callback for each read transaction that has exceeded the configured age,
+    // and has not already been warned about. Then expires the read transactions which have exceeded
+    // the maximum age
+    pub(crate) fn check_read_transaction_ages(&self, mem: &TransactionalMemory) {
+        if let Some((warning_age, callback)) = &self.read_transaction_warning {
+            let mut old = vec![];
+            {
+                let mut state = self.state.lock().unwrap();
+                for transaction in state.user_read_transactions.values_mut() {
+                    if !transaction.warned && transaction.started.elapsed() >= *warning_age {
+                        transaction.warned = true;
+                        old.push(transaction.info());
+                    }
+                }
+            }
+            // Invoke the callback without holding the lock, so that it may call back into the
+            // database
+            for info in old {
+                callback(&info);
+            }
+        }
+
+        if let Some(max_age) = self.max_read_transaction_age {
+            let mut state = self.state.lock().unwrap();
+            let mut expired = vec![];
+            for transaction in state.user_read_transactions.values() {
+                if !transaction.expired.load(AtomicOrdering::Acquire)
+                    && transaction.started.elapsed() >= max_age
+                {
+                    // The flag must be set before the reference is released, so that a reader
+                    // never observes a page which was reused after its snapshot was released
+                    transaction.expired.store(true, AtomicOrdering::Release);
+                    expired.push(transaction.transaction_id);
+                }
+            }
+            for id in expired {
+                #[cfg(feature = "logging")]
+                debug!("Expiring read transaction id={id:?}");
+                mem.read_snapshot_released();
+                state.release_read_reference(id);
+            }
+        }
+    }
+
+    pub(crate) fn deallocate_read_transaction(&self, id: TransactionId) {
+        self.state.lock().unwrap().release_read_reference(id);
+    }
+
     pub(crate) fn any_savepoint_exists(&self) -> bool {
         !self.state.lock().unwrap().valid_savepoints.is_empty()
     }
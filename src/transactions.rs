@@ -7,8 +7,8 @@ use crate::transaction_tracker::{SavepointId, TransactionId, TransactionTracker}
 use crate::tree_store::{
     AllocationPolicy, Btree, BtreeHeader, BtreeMut, InternalTableDefinition, MAX_PAIR_LENGTH,
     MAX_VALUE_LENGTH, Page, PageAllocator, PageHint, PageListMut, PageNumber, PageResolver,
-    PageTrackerPolicy, SerializedSavepoint, ShrinkPolicy, TableTree, TableTreeMut, TableType,
-    TransactionalMemory,
+    PageTrackerPolicy, SerializedSavepoint, ShrinkPolicy, SpaceStats, Stopwatch, TableTree,
+    TableTreeMut, TableType, TransactionalMemory,
 };
 use crate::types::{Key, Value};
 use crate::{
@@ -20,6 +20,7 @@ use crate::{
 };
 #[cfg(feature = "logging")]
 use log::{debug, warn};
+use std::backtrace::Backtrace;
 use std::borrow::Borrow;
 use std::cmp::min;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
@@ -29,9 +30,10 @@ use std::mem::size_of;
 use std::ops::{RangeBounds, RangeFull};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::{panic, thread};
 
-const MAX_PAGES_PER_COMPACTION: usize = 1_000_000;
+pub(crate) const MAX_PAGES_PER_COMPACTION: usize = 1_000_000;
 const NEXT_SAVEPOINT_TABLE: SystemTableDefinition<(), SavepointId> =
     SystemTableDefinition::new("next_savepoint_id");
 pub(crate) const SAVEPOINT_TABLE: SystemTableDefinition<SavepointId, SerializedSavepoint> =
@@ -66,7 +68,7 @@ pub(crate) struct PageList<'a> {
 }
 
 impl PageList<'_> {
-    fn required_bytes(len: usize) -> usize {
+    pub(crate) fn required_bytes(len: usize) -> usize {
         2 + PageNumber::serialized_size() * len
     }
 
@@ -358,6 +360,35 @@ impl DatabaseStats {
     }
 }
 
+/// Information about a live [`ReadTransaction`]
+///
+/// See [`crate::Database::live_read_transactions`]
+#[derive(Clone, Debug)]
+pub struct ReadTransactionInfo {
+    pub(crate) transaction_id: u64,
+    pub(crate) age: Duration,
+    pub(crate) backtrace: Option<Arc<Backtrace>>,
+}
+
+impl ReadTransactionInfo {
+    /// Id of the committed transaction whose snapshot is being read. Pages freed after this
+    /// transaction cannot be reused until the read transaction is dropped, or expires
+    pub fn transaction_id(&self) -> u64 {
+        self.transaction_id
+    }
+
+    /// Time since the read transaction began. Always zero on `wasm32-unknown-unknown`
+    pub fn age(&self) -> Duration {
+        self.age
+    }
+
+    /// Backtrace of the call that began the read transaction, if enabled with
+    /// [`crate::Builder::set_read_transaction_backtraces`]
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_deref()
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 #[non_exhaustive]
 pub enum Durability {
@@ -673,7 +704,9 @@ impl TableNamespace {
             length,
             self.freed_pages.clone(),
             self.allocated_pages.clone(),
-            self.table_tree.page_allocator().clone(),
+            self.table_tree
+                .page_allocator()
+                .with_io_counters(transaction.mem.table_io_counters(definition.name())),
             transaction,
         ))
     }
@@ -694,7 +727,9 @@ impl TableNamespace {
             root,
             self.freed_pages.clone(),
             self.allocated_pages.clone(),
-            self.table_tree.page_allocator().clone(),
+            self.table_tree
+                .page_allocator()
+                .with_io_counters(transaction.mem.table_io_counters(definition.name())),
             transaction,
         ))
     }
@@ -877,6 +912,8 @@ pub struct WriteTransaction {
     // All transaction-local savepoint lifecycle state. See
     // `SavepointTransactionState` for the commit/abort contract.
     savepoint_state: Mutex<SavepointTransactionState>,
+    // Total bytes written to storage when the transaction began
+    initial_bytes_written: u64,
 }
 
 impl WriteTransaction {
@@ -896,7 +933,7 @@ impl WriteTransaction {
         let tables = TableNamespace::new(root_page, guard.clone(), page_allocator.clone());
         let system_tables = SystemNamespace::new(system_page, guard.clone(), page_allocator);
 
-        Ok(Self {
+        let transaction = Self {
             transaction_tracker,
             mem: mem.clone(),
             transaction_guard: guard.clone(),
@@ -912,7 +949,15 @@ impl WriteTransaction {
             post_commit_free: PostCommitFree::Enabled,
             shrink_policy: ShrinkPolicy::Default,
             savepoint_state: Mutex::new(SavepointTransactionState::default()),
-        })
+            initial_bytes_written: mem.bytes_written(),
+        };
+        if mem.max_file_len().is_some() {
+            let mut freeable = 0;
+            transaction.for_each_freeable_page(|_| freeable += 1)?;
+            transaction.page_allocator().set_freeable_pages(freeable);
+        }
+
+        Ok(transaction)
     }
 
     pub(crate) fn set_shrink_policy(&mut self, shrink_policy: ShrinkPolicy) {
@@ -1087,6 +1132,7 @@ impl WriteTransaction {
 
         let mut savepoint = self.ephemeral_savepoint()?;
 
+        let page_allocator = self.page_allocator();
         let mut system_tables = self.system_tables.lock().unwrap();
 
         let mut next_table = system_tables.open_system_table(self, NEXT_SAVEPOINT_TABLE)?;
@@ -1094,10 +1140,13 @@ impl WriteTransaction {
         drop(next_table);
 
         let mut savepoint_table = system_tables.open_system_table(self, SAVEPOINT_TABLE)?;
-        savepoint_table.insert(
-            savepoint.get_id(),
-            SerializedSavepoint::from_savepoint(&savepoint),
-        )?;
+        let result = savepoint_table
+            .insert(
+                savepoint.get_id(),
+                SerializedSavepoint::from_savepoint(&savepoint),
+            )
+            .map(drop);
+        page_allocator.check_limit(result)?;
 
         savepoint.set_persistent();
         self.transaction_tracker
@@ -1260,6 +1309,15 @@ impl WriteTransaction {
         assert_eq!(self.mem.get_version(), savepoint.get_version());
         self.dirty.store(true, Ordering::Release);
 
+        let result = self.restore_savepoint_helper(savepoint);
+        // The restore is incomplete, if it ran out of space partway through
+        if let Err(SavepointError::Storage(err)) = &result {
+            self.page_allocator().check_limit_error(err);
+        }
+        result
+    }
+
+    fn restore_savepoint_helper(&mut self, savepoint: &Savepoint) -> Result<(), SavepointError> {
         // Restoring a savepoint needs to accomplish the following:
         // 1) restore the table tree. This is trivial, since we have the old root
         // 1a) we also filter the freed tree to remove any pages referenced by the old root
@@ -1366,6 +1424,10 @@ impl WriteTransaction {
             Durability::None => InternalDurability::None,
             Durability::Immediate => InternalDurability::Immediate,
         };
+        // A non-durable commit doesn't free the pages freed by earlier transactions
+        if matches!(self.durability, InternalDurability::None) {
+            self.page_allocator().set_freeable_pages(0);
+        }
 
         Ok(())
     }
@@ -1562,10 +1624,87 @@ impl WriteTransaction {
             self.abort_inner()?;
             return Err(CommitError::TransactionPoisoned);
         }
-        self.commit_inner()
+        if let Err(err) = self.reserve_commit_space() {
+            self.abort_inner()?;
+            return Err(err.into());
+        }
+        let stopwatch = Stopwatch::start();
+        self.commit_inner()?;
+        self.mem.record_commit(
+            stopwatch.elapsed(),
+            self.mem.bytes_written() - self.initial_bytes_written,
+        );
+
+        Ok(())
+    }
+
+    // Checks that the commit fits within the file size limit, before it changes anything
+    fn reserve_commit_space(&self) -> Result {
+        let (freed, tables) = {
+            let tables = self.tables.lock().unwrap();
+            let freed = tables.freed_pages.lock().unwrap().len();
+            (freed, tables.table_tree.pending_update_count())
+        };
+        let system_freed = self
+            .system_tables
+            .lock()
+            .unwrap()
+            .system_freed_pages()
+            .lock()
+            .unwrap()
+            .len();
+        let mut freeable = vec![];
+        if self.mem.max_file_len().is_some() {
+            self.for_each_freeable_page(|page| freeable.push(page))?;
+        }
+        self.page_allocator().reserve_commit_space(
+            (freed + system_freed).try_into().unwrap(),
+            tables.try_into().unwrap(),
+            self.quick_repair,
+            &freeable,
+        )
+    }
+
+    // Visits the pages freed by earlier transactions, which a durable commit frees before it writes
+    // most of its metadata. See `durable_commit()`
+    fn for_each_freeable_page(&self, mut visit: impl FnMut(PageNumber)) -> Result {
+        if !matches!(self.durability, InternalDurability::Immediate) {
+            return Ok(());
+        }
+        let free_until = self
+            .transaction_tracker
+            .oldest_live_read_transaction()
+            .map_or(self.transaction_id, |x| x.next());
+        for definition in [DATA_FREED_TABLE, SYSTEM_FREED_TABLE] {
+            self.read_existing_system_table(definition, |table| {
+                let key = TransactionIdWithPagination {
+                    transaction_id: free_until.raw_id(),
+                    pagination_id: 0,
+                };
+                for entry in table.range(&(..key))? {
+                    let entry = entry?;
+                    let page_list = entry.value();
+                    for i in 0..page_list.len() {
+                        visit(page_list.get(i));
+                    }
+                }
+                Ok(())
+            })?;
+        }
+        Ok(())
     }
 
     fn commit_inner(&mut self) -> Result<(), CommitError> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            "commit",
+            transaction_id = self.transaction_id.raw_id(),
+            durability = ?self.durability,
+            pages_written = tracing::field::Empty,
+            bytes_written = tracing::field::Empty,
+        )
+        .entered();
+        self.page_allocator().disable_capacity_checks();
         // Quick-repair requires 2-phase commit
         if self.quick_repair {
             self.two_phase_commit = true;
@@ -1582,6 +1721,8 @@ impl WriteTransaction {
             self.transaction_id, self.durability, self.two_phase_commit, self.quick_repair
         );
         let allocated_pages: Vec<PageNumber> = allocated_pages.into_iter().collect();
+        #[cfg(feature = "tracing")]
+        span.record("pages_written", allocated_pages.len());
         match self.durability {
             InternalDurability::None => {
                 self.non_durable_commit(user_root, allocated_pages, stored_data_freed_pages)?;
@@ -1614,6 +1755,11 @@ impl WriteTransaction {
             "Finished commit of transaction id={:?}",
             self.transaction_id
         );
+        #[cfg(feature = "tracing")]
+        span.record(
+            "bytes_written",
+            self.mem.bytes_written() - self.initial_bytes_written,
+        );
 
         Ok(())
     }
@@ -1634,7 +1780,7 @@ impl WriteTransaction {
         let page_allocator = self.page_allocator();
         while !freed_pages.is_empty() {
             let chunk_size = 400;
-            let buffer_size = PageList::required_bytes(chunk_size);
+            let buffer_size = PageList::required_bytes(min(freed_pages.len(), chunk_size));
             let key = TransactionIdWithPagination {
                 transaction_id: self.transaction_id.raw_id(),
                 pagination_id: pagination_counter,
@@ -1730,7 +1876,7 @@ impl WriteTransaction {
         let mut pagination_counter = 0;
         while !pages.is_empty() {
             let chunk_size = 400;
-            let buffer_size = PageList::required_bytes(chunk_size);
+            let buffer_size = PageList::required_bytes(min(pages.len(), chunk_size));
             let key = TransactionIdWithPagination {
                 transaction_id: transaction_id.raw_id(),
                 pagination_id: pagination_counter,
@@ -1868,6 +2014,8 @@ impl WriteTransaction {
         self.apply_savepoint_state_on_commit();
 
         if self.post_commit_free == PostCommitFree::Enabled {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("drain_pending_free_pages").entered();
             self.process_data_freed_pages_after_commit(user_root, &page_allocator)?;
         }
 
@@ -2014,31 +2162,55 @@ impl WriteTransaction {
         Ok(())
     }
 
-    // Relocate pages to lower number regions/pages
-    // Returns true if a page(s) was moved
-    pub(crate) fn compact_pages(&mut self) -> Result<bool> {
+    // Relocate up to `max_pages` of the highest pages, along with their parents, to lower number
+    // regions/pages. Returns true if a page(s) was moved
+    pub(crate) fn compact_pages(&mut self, max_pages: usize) -> Result<bool> {
         let mut progress = false;
 
-        // Find the 1M highest pages
-        let mut highest_pages = BTreeMap::new();
+        // Find the highest pages. Pages of the data tree are tagged with `false`, and those of the
+        // system tree with `true`
         let mut tables = self.tables.lock().unwrap();
+        let tables = &mut *tables;
         let table_tree = &mut tables.table_tree;
-        table_tree.highest_index_pages(MAX_PAGES_PER_COMPACTION, &mut highest_pages)?;
+        let mut data_pages = BTreeMap::new();
+        table_tree.highest_index_pages(max_pages, &mut data_pages)?;
         let mut system_tables = self.system_tables.lock().unwrap();
         let system_table_tree = &mut system_tables.table_tree;
-        system_table_tree.highest_index_pages(MAX_PAGES_PER_COMPACTION, &mut highest_pages)?;
+        let mut system_pages = BTreeMap::new();
+        system_table_tree.highest_index_pages(max_pages, &mut system_pages)?;
+        let mut highest_pages: BTreeMap<PageNumber, (_, bool)> = data_pages
+            .into_iter()
+            .map(|(page, path)| (page, (path, false)))
+            .chain(
+                system_pages
+                    .into_iter()
+                    .map(|(page, path)| (page, (path, true))),
+            )
+            .collect();
+        while highest_pages.len() > max_pages {
+            highest_pages.pop_first();
+        }
 
         let page_allocator = table_tree.page_allocator().clone();
 
-        // Calculate how many of them can be relocated to lower pages, starting from the last page
+        // Calculate how many of them can be relocated to lower pages, starting from the last page.
+        // Allocations for the data tree must be tracked, so that they are freed if a savepoint from
+        // before this transaction is restored
         let mut relocation_map = HashMap::new();
-        for path in highest_pages.into_values().rev() {
+        for (path, system) in highest_pages.into_values().rev() {
             if relocation_map.contains_key(&path.page_number()) {
                 continue;
             }
+            let mut data_allocated = tables.allocated_pages.lock().unwrap();
+            let mut ignore = PageTrackerPolicy::Ignore;
+            let allocated = if system {
+                &mut ignore
+            } else {
+                &mut *data_allocated
+            };
             let old_page = page_allocator.get_page(path.page_number(), PageHint::None)?;
-            let mut new_page = page_allocator
-                .allocate_lowest(old_page.memory().len(), &mut PageTrackerPolicy::Ignore)?;
+            let mut new_page =
+                page_allocator.allocate_lowest(old_page.memory().len(), allocated)?;
             let new_page_number = new_page.get_page_number();
             // We have to copy at least the page type into the new page.
             // Otherwise its cache priority will be calculated incorrectly
@@ -2052,10 +2224,8 @@ impl WriteTransaction {
                         continue;
                     }
                     let old_parent = page_allocator.get_page(*parent, PageHint::None)?;
-                    let mut new_page = page_allocator.allocate_lowest(
-                        old_parent.memory().len(),
-                        &mut PageTrackerPolicy::Ignore,
-                    )?;
+                    let mut new_page =
+                        page_allocator.allocate_lowest(old_parent.memory().len(), allocated)?;
                     let new_page_number = new_page.get_page_number();
                     // We have to copy at least the page type into the new page.
                     // Otherwise its cache priority will be calculated incorrectly
@@ -2064,7 +2234,7 @@ impl WriteTransaction {
                     relocation_map.insert(*parent, new_page_number);
                 }
             } else {
-                page_allocator.free(new_page_number, &mut PageTrackerPolicy::Ignore);
+                page_allocator.free(new_page_number, allocated);
                 break;
             }
         }
@@ -2320,6 +2490,18 @@ impl WriteTransaction {
             .map_or(0, |entry| entry.key().pagination_id + 1))
     }
 
+    /// Number of bytes of modified pages which are currently buffered in memory
+    ///
+    /// This reads the write buffer of the database, rather than a count kept by the transaction.
+    /// Every commit, durable or not, empties the buffer, so it only holds pages of the open write
+    /// transaction. Pages which were already written early, because the budget was exceeded, are
+    /// not counted.
+    ///
+    /// See [`crate::Builder::set_dirty_page_budget`]
+    pub fn dirty_bytes(&self) -> usize {
+        self.mem.dirty_bytes()
+    }
+
     /// Retrieves information about storage usage in the database
     pub fn stats(&self) -> Result<DatabaseStats> {
         let tables = self.tables.lock().unwrap();
@@ -2349,6 +2531,38 @@ impl WriteTransaction {
         })
     }
 
+    /// Retrieves a map of the free space in the database file, including the allocations made by
+    /// this transaction
+    ///
+    /// Unlike [`DatabaseStats::fragmented_bytes`], this reports where the free space is, which can
+    /// be used to decide whether [`crate::Database::compact`] is worth running
+    pub fn space_stats(&self) -> Result<SpaceStats> {
+        let mut pending_free_pages = 0;
+        for definition in [DATA_FREED_TABLE, SYSTEM_FREED_TABLE] {
+            pending_free_pages += self
+                .read_existing_system_table(definition, |table| {
+                    let mut pages = 0u64;
+                    for entry in table.range::<RangeFull, TransactionIdWithPagination>(&..)? {
+                        let entry = entry?;
+                        let page_list = entry.value();
+                        for i in 0..page_list.len() {
+                            pages += 1 << page_list.get(i).page_order;
+                        }
+                    }
+                    Ok(pages)
+                })?
+                .unwrap_or(0);
+        }
+
+        Ok(SpaceStats {
+            file_len: self.mem.file_len(),
+            page_size: self.mem.get_page_size(),
+            regions: self.mem.region_space_stats(),
+            pending_free_pages,
+            shrinkable_bytes: self.mem.shrinkable_bytes(),
+        })
+    }
+
     #[allow(dead_code)]
     pub(crate) fn print_debug(&self) -> Result {
         // Flush any pending updates to make sure we get the latest root
@@ -2417,6 +2631,7 @@ impl Drop for WriteTransaction {
 pub struct ReadTransaction {
     mem: Arc<TransactionalMemory>,
     tree: TableTree,
+    snapshot_expired: Option<Arc<AtomicBool>>,
 }
 
 impl ReadTransaction {
@@ -2425,15 +2640,24 @@ impl ReadTransaction {
         guard: TransactionGuard,
     ) -> Result<Self, TransactionError> {
         let root_page = mem.get_data_root();
+        let snapshot_expired = guard.snapshot_expired();
         let guard = Arc::new(guard);
-        let resolver = PageResolver::new(mem.clone());
+        let resolver =
+            PageResolver::new(mem.clone()).with_snapshot_expiry(snapshot_expired.clone());
         Ok(Self {
             mem,
             tree: TableTree::new(root_page, PageHint::Clean, guard, resolver)
                 .map_err(TransactionError::Storage)?,
+            snapshot_expired,
         })
     }
 
+    fn table_resolver(&self, name: &str) -> PageResolver {
+        PageResolver::new(self.mem.clone())
+            .with_io_counters(self.mem.table_io_counters(name))
+            .with_snapshot_expiry(self.snapshot_expired.clone())
+    }
+
     /// Open the given table
     pub fn open_table<K: Key + 'static, V: Value + 'static>(
         &self,
@@ -2448,9 +2672,9 @@ impl ReadTransaction {
             InternalTableDefinition::Normal { table_root, .. } => Ok(ReadOnlyTable::new(
                 definition.name().to_string(),
                 table_root,
-                PageHint::Clean,
+                self.mem.table_page_hint(definition.name()),
                 self.tree.transaction_guard().clone(),
-                PageResolver::new(self.mem.clone()),
+                self.table_resolver(definition.name()),
             )?),
             InternalTableDefinition::Multimap { .. } => unreachable!(),
         }
@@ -2476,10 +2700,10 @@ impl ReadTransaction {
             } => Ok(ReadOnlyUntypedTable::new(
                 name,
                 table_root,
-                PageHint::Clean,
+                self.mem.table_page_hint(name),
                 fixed_key_size,
                 fixed_value_size,
-                PageResolver::new(self.mem.clone()),
+                self.table_resolver(name),
             )),
             InternalTableDefinition::Multimap { .. } => unreachable!(),
         }
@@ -2507,7 +2731,7 @@ impl ReadTransaction {
                 table_length,
                 PageHint::Clean,
                 self.tree.transaction_guard().clone(),
-                PageResolver::new(self.mem.clone()),
+                self.table_resolver(definition.name()),
             )?),
         }
     }
@@ -2538,7 +2762,7 @@ impl ReadTransaction {
                 PageHint::Clean,
                 fixed_key_size,
                 fixed_value_size,
-                PageResolver::new(self.mem.clone()),
+                self.table_resolver(name),
             )),
         }
     }
@@ -22,6 +22,12 @@ use std::marker::PhantomData;
 use std::ops::RangeBounds;
 use std::sync::{Arc, Mutex};
 
+// Number of single page allocations that `PageAllocator::ensure_capacity()` checks there is likely
+// room for before a modification. Enough to copy and split every branch on the path from the root,
+// for any realistic tree height. Insertions check for twice as many, so that once they run into the
+// file size limit, there is still room to remove data
+pub(crate) const MODIFICATION_PAGES: usize = 16;
+
 pub(crate) struct BtreeStats {
     pub(crate) tree_height: u32,
     pub(crate) leaf_pages: u64,
@@ -311,14 +317,15 @@ impl UntypedBtreeMut {
         }
 
         let mut freed_pages = self.freed_pages.lock().unwrap();
-        // No need to track allocations, because this method is only called during compaction when
-        // there can't be any savepoints
+        // No need to track allocations, because this method is only called by compaction at the
+        // start of a write transaction, when none of the pages in the tree are uncommitted
         let mut ignore = PageTrackerPolicy::Ignore;
         if !self
             .page_allocator
             .free_if_uncommitted(page_number, &mut ignore)
         {
-            freed_pages.push(page_number);
+            self.page_allocator
+                .defer_free(page_number, &mut freed_pages);
         }
 
         Ok(Some((new_page.get_page_number(), DEFERRED)))
@@ -424,6 +431,10 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
             key,
             V::as_bytes(value).as_ref().len()
         );
+        self.page_allocator.ensure_capacity(
+            || K::as_bytes(key).as_ref().len() + V::as_bytes(value).as_ref().len(),
+            2 * MODIFICATION_PAGES,
+        )?;
         // The cursor updates the root incrementally, so pages queued before an
         // error still need to be recorded for commit.
         let mut freed_pages = self.freed_pages.lock().unwrap();
@@ -482,6 +493,8 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
     pub(crate) fn remove(&mut self, key: &K::SelfType<'_>) -> Result<Option<AccessGuard<'_, V>>> {
         #[cfg(feature = "logging")]
         trace!("Btree(root={:?}): Deleting {:?}", &self.root, key);
+        self.page_allocator
+            .ensure_capacity(|| 0, MODIFICATION_PAGES)?;
         let mut freed_pages = self.freed_pages.lock().unwrap();
         let mut operation: MutateHelper<'_, '_, K, V> = MutateHelper::new(
             &mut self.root,
@@ -495,6 +508,8 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
 
     // Removes and returns the leftmost entry in the tree, if any, in a single tree descent.
     pub(crate) fn pop_first(&mut self) -> Result<Option<(AccessGuard<'_, K>, AccessGuard<'_, V>)>> {
+        self.page_allocator
+            .ensure_capacity(|| 0, MODIFICATION_PAGES)?;
         let mut freed_pages = self.freed_pages.lock().unwrap();
         let mut cursor: CursorMut<'_, '_, K, V> = CursorMut::new(
             &mut self.root,
@@ -508,6 +523,8 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
 
     // Removes and returns the rightmost entry in the tree, if any, in a single tree descent.
     pub(crate) fn pop_last(&mut self) -> Result<Option<(AccessGuard<'_, K>, AccessGuard<'_, V>)>> {
+        self.page_allocator
+            .ensure_capacity(|| 0, MODIFICATION_PAGES)?;
         let mut freed_pages = self.freed_pages.lock().unwrap();
         let mut cursor: CursorMut<'_, '_, K, V> = CursorMut::new(
             &mut self.root,
@@ -568,7 +585,7 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
                 let old_page = self.page_allocator.get_page(root.root, PageHint::None)?;
                 new_page.memory_mut().copy_from_slice(old_page.memory());
                 drop(old_page);
-                freed_pages.push(root.root);
+                self.page_allocator.defer_free(root.root, &mut freed_pages);
 
                 root.root = new_page.get_page_number();
                 root.checksum = DEFERRED;
@@ -629,7 +646,7 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
                         .memory_mut()
                         .copy_from_slice(old_child_page.memory());
                     drop(old_child_page);
-                    freed_pages.push(child_page);
+                    self.page_allocator.defer_free(child_page, &mut freed_pages);
 
                     let mut mutator = BranchMutator::new(page.memory_mut());
                     mutator.write_child_page(child_index, new_page.get_page_number(), DEFERRED);
@@ -663,6 +680,17 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
         self.read_tree()?.range(range)
     }
 
+    pub(crate) fn range_with<'a0, T: RangeBounds<KR> + 'a0, KR: Borrow<K::SelfType<'a0>> + 'a0>(
+        &self,
+        range: &'_ T,
+        fill_cache: bool,
+    ) -> Result<BtreeCursorRange<K, V>>
+    where
+        K: 'a0,
+    {
+        self.read_tree()?.range_with(range, fill_cache)
+    }
+
     pub(crate) fn extract_from_if<
         'a,
         'a0,
@@ -817,6 +845,10 @@ impl<K: Key + 'static, V: MutInPlaceValue + 'static> BtreeMut<K, V> {
             "Btree(root={:?}): Inserting {:?} with {} reserved bytes for the value",
             &self.root, key, value_length
         );
+        self.page_allocator.ensure_capacity(
+            || K::as_bytes(key).as_ref().len() + value_length,
+            2 * MODIFICATION_PAGES,
+        )?;
         let mut freed_pages = self.freed_pages.lock().unwrap();
         let mut value = vec![0u8; value_length];
         V::initialize(&mut value);
@@ -874,6 +906,52 @@ impl RawBtree {
         Ok(self.root.map_or(0, |x| x.length))
     }
 
+    // Reads the branch pages into the cache, one level at a time starting from the root, until
+    // `budget` bytes have been read. Returns the leaf pages which were not read, in key order
+    pub(crate) fn prefetch_branches(&self, budget: &mut u64) -> Result<Vec<PageNumber>> {
+        let Some(root) = self.root else {
+            return Ok(vec![]);
+        };
+        let mut level = vec![root.root];
+        loop {
+            let mut children = vec![];
+            for (i, page_number) in level.iter().enumerate() {
+                if *budget == 0 {
+                    return Ok(vec![]);
+                }
+                let page = self.mem.get_page(*page_number, self.hint)?;
+                *budget = budget.saturating_sub(page.memory().len().try_into().unwrap());
+                match page.memory()[0] {
+                    LEAF => {
+                        // All leaves are at the same depth, so the rest of this level are leaves
+                        debug_assert_eq!(i, 0);
+                        return Ok(level[1..].to_vec());
+                    }
+                    BRANCH => {
+                        let accessor = BranchAccessor::new(&page, self.fixed_key_size);
+                        for j in 0..accessor.count_children() {
+                            children.push(accessor.child_page(j).unwrap());
+                        }
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            level = children;
+        }
+    }
+
+    // Reads the given pages into the cache, until `budget` bytes have been read
+    pub(crate) fn prefetch_pages(&self, pages: &[PageNumber], budget: &mut u64) -> Result {
+        for page_number in pages {
+            if *budget == 0 {
+                break;
+            }
+            let page = self.mem.get_page(*page_number, self.hint)?;
+            *budget = budget.saturating_sub(page.memory().len().try_into().unwrap());
+        }
+        Ok(())
+    }
+
     pub(crate) fn verify_checksum(&self) -> Result<bool> {
         if let Some(header) = self.root {
             self.verify_checksum_helper(header.root, header.checksum)
@@ -1095,12 +1173,21 @@ impl<K: Key, V: Value> Btree<K, V> {
         &self,
         range: &'_ T,
     ) -> Result<BtreeCursorRange<K, V>> {
-        BtreeCursorRange::new(
-            range,
-            self.root.map(|x| x.root),
-            self.mem.clone(),
-            self.hint,
-        )
+        self.range_with(range, true)
+    }
+
+    // If `fill_cache` is false, pages read by the iterator are not inserted into the read cache
+    pub(crate) fn range_with<'a0, T: RangeBounds<KR>, KR: Borrow<K::SelfType<'a0>>>(
+        &self,
+        range: &'_ T,
+        fill_cache: bool,
+    ) -> Result<BtreeCursorRange<K, V>> {
+        let hint = if fill_cache {
+            self.hint
+        } else {
+            self.hint.without_cache_fill()
+        };
+        BtreeCursorRange::new(range, self.root.map(|x| x.root), self.mem.clone(), hint)
     }
 
     pub(crate) fn len(&self) -> Result<u64> {
@@ -2034,7 +2034,9 @@ impl<'b> BranchMutator<'b> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tree_store::{AllocationPolicy, InMemoryBackend, PAGE_SIZE, TransactionalMemory};
+    use crate::tree_store::{
+        AllocationPolicy, CachePolicy, InMemoryBackend, PAGE_SIZE, TransactionalMemory,
+    };
 
     const MAX_PAIRS: usize = u16::MAX as usize;
 
@@ -2049,6 +2051,8 @@ mod tests {
             page_size,
             None,
             0,
+            CachePolicy::Lru,
+            0,
             false,
         )
         .unwrap();
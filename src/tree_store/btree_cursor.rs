@@ -1239,7 +1239,7 @@ impl<'a, K: Key + 'static, V: Value + 'static> RangeMut<'a, K, V> {
                 .page_allocator
                 .free_if_uncommitted(page, &mut allocated)
             {
-                master_free_list.push(page);
+                self.page_allocator.defer_free(page, &mut master_free_list);
             }
         }
     }
@@ -1291,7 +1291,8 @@ mod tests {
     use super::*;
     use crate::tree_store::btree_base::LeafBuilder;
     use crate::tree_store::{
-        AllocationPolicy, InMemoryBackend, PAGE_SIZE, PageTrackerPolicy, TransactionalMemory,
+        AllocationPolicy, CachePolicy, InMemoryBackend, PAGE_SIZE, PageTrackerPolicy,
+        TransactionalMemory,
     };
 
     fn cursor_with_entries(entries: &[u64]) -> Cursor<u64, u64> {
@@ -1301,6 +1302,8 @@ mod tests {
             PAGE_SIZE,
             None,
             0,
+            CachePolicy::Lru,
+            0,
             false,
         )
         .unwrap();
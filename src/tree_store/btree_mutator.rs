@@ -114,7 +114,7 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
             .page_allocator
             .free_if_uncommitted(page_number, &mut allocated)
         {
-            self.freed.push(page_number);
+            self.page_allocator.defer_free(page_number, self.freed);
         }
     }
 
@@ -446,7 +446,7 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
                             self.page_allocator.free(page_number, &mut allocated);
                             Some(AccessGuard::with_arc_page(arc, start..end))
                         } else {
-                            self.freed.push(page_number);
+                            self.page_allocator.defer_free(page_number, self.freed);
                             Some(AccessGuard::with_page(page, start..end))
                         }
                     } else {
@@ -481,7 +481,7 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
                             self.page_allocator.free(page_number, &mut allocated);
                             Some(AccessGuard::with_arc_page(arc, start..end))
                         } else {
-                            self.freed.push(page_number);
+                            self.page_allocator.defer_free(page_number, self.freed);
                             Some(AccessGuard::with_page(page, start..end))
                         }
                     } else {
@@ -765,7 +765,8 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
             // Won't be freed until the end of the transaction, so returning the page
             // in the AccessGuard below is still safe
             let key_guard = want_key.then(|| AccessGuard::with_page(page.clone(), key_range));
-            self.freed.push(page.get_page_number());
+            self.page_allocator
+                .defer_free(page.get_page_number(), self.freed);
             (key_guard, AccessGuard::with_page(page, value_range))
         };
         Ok((result, key_guard, value_guard))
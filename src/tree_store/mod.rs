@@ -10,7 +10,7 @@ mod page_store;
 mod table_tree;
 mod table_tree_base;
 
-pub(crate) use btree::{Btree, BtreeMut, BtreeStats, RawBtree};
+pub(crate) use btree::{Btree, BtreeMut, BtreeStats, MODIFICATION_PAGES, RawBtree};
 pub(crate) use btree_base::BtreeHeader;
 pub use btree_base::{AccessGuard, AccessGuardMut, AccessGuardMutInPlace};
 pub(crate) use btree_base::{BRANCH, LEAF, LeafAccessor, RawLeafBuilder};
@@ -20,10 +20,14 @@ pub(crate) use extract_if::BtreeExtractIf;
 pub(crate) use multimap_btree::{DynamicCollection, DynamicCollectionType, multimap_btree_stats};
 pub(crate) use page_store::ReadOnlyBackend;
 pub(crate) use page_store::{
-    AllocationPolicy, FILE_FORMAT_VERSION3, MAX_PAIR_LENGTH, MAX_VALUE_LENGTH, PAGE_SIZE, Page,
-    PageAllocator, PageHint, PageNumber, PageNumberHashSet, PageResolver, PageTrackerPolicy,
-    SerializedSavepoint, ShrinkPolicy, TransactionalMemory,
+    AllocationPolicy, FILE_FORMAT_VERSION3, MAX_PAGE_SIZE, MAX_PAIR_LENGTH, MAX_VALUE_LENGTH,
+    MIN_PAGE_SIZE, PAGE_SIZE, Page, PageAllocator, PageHint, PageNumber, PageNumberHashSet,
+    PageResolver, PageTrackerPolicy, SerializedSavepoint, ShrinkPolicy, Stopwatch,
+    TransactionalMemory,
+};
+pub use page_store::{
+    CachePolicy, Histogram, InMemoryBackend, IoStats, RegionSpaceStats, Savepoint, SpaceStats,
+    TableIoStats, file_backend,
 };
-pub use page_store::{InMemoryBackend, Savepoint, file_backend};
 pub(crate) use table_tree::{PageListMut, TableTree, TableTreeMut};
 pub(crate) use table_tree_base::{InternalTableDefinition, TableType};
@@ -272,7 +272,7 @@ pub(super) fn relocate_subtrees(
     // there can't be any savepoints
     let mut ignore = PageTrackerPolicy::Ignore;
     if !page_allocator.free_if_uncommitted(old_page_number, &mut ignore) {
-        freed_pages.lock().unwrap().push(old_page_number);
+        page_allocator.defer_free(old_page_number, &mut freed_pages.lock().unwrap());
     }
     Ok((new_page_number, DEFERRED))
 }
@@ -0,0 +1,232 @@
+use crate::tree_store::page_store::cache_policy::{GhostList, compact_queue};
+use crate::tree_store::page_store::fast_hash::FastHashMapU64;
+use std::cmp::{max, min};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+struct Entry<T> {
+    value: T,
+    // Sequence number of this entry's position in its clock
+    sequence: u64,
+    // True if the entry is in the frequency clock, rather than the recency clock
+    frequent: bool,
+    referenced: AtomicBool,
+}
+
+// CAR, from "CAR: Clock with Adaptive Replacement" by Bansal & Modha. This is ARC, with the LRU
+// lists replaced by clocks, so that hits only need a shared reference.
+//
+// There is no fixed capacity, since the caller evicts entries as needed to stay within its byte
+// budget, so the number of entries currently cached is used in its place.
+#[derive(Default)]
+pub(super) struct AdaptiveCache<T> {
+    cache: FastHashMapU64<Entry<T>>,
+    // T1: pages which have been read once since they entered the cache
+    recent: VecDeque<(u64, u64)>,
+    recent_len: usize,
+    // T2: pages which have been read more than once
+    frequent: VecDeque<(u64, u64)>,
+    // B1 & B2: keys recently evicted from T1 and T2 respectively
+    recent_ghosts: GhostList,
+    frequent_ghosts: GhostList,
+    // Target number of entries in T1
+    recent_target: usize,
+    next_sequence: u64,
+}
+
+impl<T> AdaptiveCache<T> {
+    pub(super) fn new() -> Self {
+        Self {
+            cache: FastHashMapU64::default(),
+            recent: VecDeque::default(),
+            recent_len: 0,
+            frequent: VecDeque::default(),
+            recent_ghosts: GhostList::default(),
+            frequent_ghosts: GhostList::default(),
+            recent_target: 0,
+            next_sequence: 0,
+        }
+    }
+
+    fn is_live(&self, key: u64, sequence: u64, frequent: bool) -> bool {
+        self.cache
+            .get(&key)
+            .is_some_and(|entry| entry.sequence == sequence && entry.frequent == frequent)
+    }
+
+    pub(super) fn insert(&mut self, key: u64, value: T) -> Option<T> {
+        if let Some(entry) = self.cache.get_mut(&key) {
+            return Some(std::mem::replace(&mut entry.value, value));
+        }
+        let capacity = self.cache.len() + 1;
+        let frequent = if self.recent_ghosts.remove(key) {
+            // T1 was too small to keep this page, so grow its target
+            let delta = max(
+                1,
+                self.frequent_ghosts.len() / (self.recent_ghosts.len() + 1),
+            );
+            self.recent_target = min(self.recent_target + delta, capacity);
+            true
+        } else if self.frequent_ghosts.remove(key) {
+            let delta = max(
+                1,
+                self.recent_ghosts.len() / (self.frequent_ghosts.len() + 1),
+            );
+            self.recent_target = self.recent_target.saturating_sub(delta);
+            true
+        } else {
+            false
+        };
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        if frequent {
+            self.frequent.push_back((key, sequence));
+        } else {
+            self.recent.push_back((key, sequence));
+            self.recent_len += 1;
+        }
+        self.cache.insert(
+            key,
+            Entry {
+                value,
+                sequence,
+                frequent,
+                referenced: AtomicBool::new(false),
+            },
+        );
+        None
+    }
+
+    pub(super) fn remove(&mut self, key: u64) -> Option<T> {
+        let entry = self.cache.remove(&key)?;
+        if entry.frequent {
+            let frequent_len = self.cache.len() - self.recent_len;
+            let cache = &self.cache;
+            compact_queue(&mut self.frequent, frequent_len, |key, sequence| {
+                cache
+                    .get(&key)
+                    .is_some_and(|entry| entry.sequence == sequence && entry.frequent)
+            });
+        } else {
+            self.recent_len -= 1;
+            let cache = &self.cache;
+            compact_queue(&mut self.recent, self.recent_len, |key, sequence| {
+                cache
+                    .get(&key)
+                    .is_some_and(|entry| entry.sequence == sequence && !entry.frequent)
+            });
+        }
+        Some(entry.value)
+    }
+
+    pub(super) fn get(&self, key: u64) -> Option<&T> {
+        let entry = self.cache.get(&key)?;
+        entry.referenced.store(true, Ordering::Release);
+        Some(&entry.value)
+    }
+
+    pub(super) fn keys(&self) -> impl ExactSizeIterator<Item = u64> + '_ {
+        self.cache.keys().copied()
+    }
+
+    fn evict(&mut self, key: u64) -> T {
+        let entry = self.cache.remove(&key).unwrap();
+        if entry.frequent {
+            self.frequent_ghosts.insert(key);
+        } else {
+            self.recent_len -= 1;
+            self.recent_ghosts.insert(key);
+        }
+        // Remember at most as many evicted keys as there are cached entries
+        let capacity = self.cache.len();
+        while self.recent_ghosts.len() + self.frequent_ghosts.len() > capacity {
+            if self.recent_len + self.recent_ghosts.len() > capacity
+                || self.frequent_ghosts.len() == 0
+            {
+                self.recent_ghosts.pop_oldest();
+            } else {
+                self.frequent_ghosts.pop_oldest();
+            }
+        }
+        self.recent_target = min(self.recent_target, capacity);
+        entry.value
+    }
+
+    pub(super) fn pop_lowest_priority(&mut self) -> Option<(u64, T)> {
+        loop {
+            let frequent_len = self.cache.len() - self.recent_len;
+            if self.recent_len > 0
+                && (self.recent_len >= max(1, self.recent_target) || frequent_len == 0)
+            {
+                let (key, sequence) = self.recent.pop_front().unwrap();
+                if !self.is_live(key, sequence, false) {
+                    continue;
+                }
+                let entry = self.cache.get_mut(&key).unwrap();
+                if *entry.referenced.get_mut() {
+                    // Read again since it entered the cache, so move it to the frequency clock
+                    *entry.referenced.get_mut() = false;
+                    entry.frequent = true;
+                    self.recent_len -= 1;
+                    self.frequent.push_back((key, sequence));
+                } else {
+                    return Some((key, self.evict(key)));
+                }
+            } else {
+                let (key, sequence) = self.frequent.pop_front()?;
+                if !self.is_live(key, sequence, true) {
+                    continue;
+                }
+                let entry = self.cache.get_mut(&key).unwrap();
+                if *entry.referenced.get_mut() {
+                    *entry.referenced.get_mut() = false;
+                    self.frequent.push_back((key, sequence));
+                } else {
+                    return Some((key, self.evict(key)));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tree_store::page_store::adaptive_cache::AdaptiveCache;
+
+    #[test]
+    fn scan_resistance() {
+        let mut cache = AdaptiveCache::new();
+        let capacity = 100;
+        let read = |cache: &mut AdaptiveCache<u64>, key| {
+            if cache.get(key).is_none() {
+                cache.insert(key, key);
+                while cache.cache.len() > capacity {
+                    cache.pop_lowest_priority().unwrap();
+                }
+            }
+        };
+
+        // Read the working set more than once
+        for _ in 0..3 {
+            for key in 0..50 {
+                read(&mut cache, key);
+            }
+        }
+
+        // A long scan does not evict the working set
+        for key in 10_000..20_000 {
+            read(&mut cache, key);
+        }
+        for key in 0..50 {
+            assert_eq!(cache.get(key), Some(&key));
+        }
+        assert_eq!(cache.keys().len(), capacity);
+
+        for key in 0..50 {
+            assert_eq!(cache.remove(key), Some(key));
+        }
+        while cache.pop_lowest_priority().is_some() {}
+        assert_eq!(cache.keys().len(), 0);
+        assert_eq!(cache.recent_len, 0);
+    }
+}
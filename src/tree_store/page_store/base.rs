@@ -277,6 +277,47 @@ impl Drop for PageMut<'_> {
 pub(crate) enum PageHint {
     None,
     Clean,
+    // Like `None` and `Clean` respectively, but pages which are not already cached are read
+    // without being inserted into the read cache
+    Uncached,
+    CleanUncached,
+    // Like `Clean`, but branch pages are pinned in the read cache, so that they are never evicted.
+    // They are pinned on behalf of the given id, which is used to unpin them
+    CleanPinned(u64),
+}
+
+impl PageHint {
+    pub(crate) fn without_cache_fill(self) -> Self {
+        match self {
+            PageHint::None | PageHint::Uncached => PageHint::Uncached,
+            PageHint::Clean | PageHint::CleanUncached | PageHint::CleanPinned(_) => {
+                PageHint::CleanUncached
+            }
+        }
+    }
+
+    // The page is known not to have pending writes
+    pub(crate) fn is_clean(self) -> bool {
+        matches!(
+            self,
+            PageHint::Clean | PageHint::CleanUncached | PageHint::CleanPinned(_)
+        )
+    }
+
+    pub(crate) fn fill_cache(self) -> bool {
+        matches!(
+            self,
+            PageHint::None | PageHint::Clean | PageHint::CleanPinned(_)
+        )
+    }
+
+    // The id that branch pages are pinned on behalf of, if they are to be pinned
+    pub(crate) fn pin_id(self) -> Option<u64> {
+        match self {
+            PageHint::CleanPinned(id) => Some(id),
+            _ => None,
+        }
+    }
 }
 
 pub(crate) enum PageTrackerPolicy {
@@ -297,6 +338,13 @@ impl PageTrackerPolicy {
         }
     }
 
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            PageTrackerPolicy::Ignore | PageTrackerPolicy::Closed => 0,
+            PageTrackerPolicy::Track(x) => x.len(),
+        }
+    }
+
     pub(super) fn remove(&mut self, page: PageNumber) {
         match self {
             PageTrackerPolicy::Ignore => {}
@@ -68,6 +68,16 @@ impl BtreeBitmap {
         result
     }
 
+    // Length of the result of `to_vec()`
+    pub(crate) fn serialized_len(&self) -> usize {
+        let data_len: usize = self
+            .heights
+            .iter()
+            .map(U64GroupedBitmap::serialized_len)
+            .sum();
+        END_OFFSETS + self.heights.len() * size_of::<u32>() + data_len
+    }
+
     pub(crate) fn to_vec(&self) -> Vec<u8> {
         let mut result = vec![];
         let height: u32 = self.heights.len().try_into().unwrap();
@@ -266,6 +276,10 @@ impl U64GroupedBitmap {
     // Format:
     // 4 bytes: number of elements
     // n bytes: serialized groups
+    fn serialized_len(&self) -> usize {
+        size_of::<u32>() + Self::required_words(self.len) * size_of::<u64>()
+    }
+
     pub fn to_vec(&self) -> Vec<u8> {
         let words = Self::required_words(self.len);
         let mut result = Vec::with_capacity(size_of::<u32>() + words * size_of::<u64>());
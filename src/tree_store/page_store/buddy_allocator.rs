@@ -1,7 +1,7 @@
 use crate::tree_store::PageNumber;
 use crate::tree_store::page_store::bitmap::BtreeBitmap;
 use crate::tree_store::page_store::page_manager::MAX_MAX_PAGE_ORDER;
-use std::cmp::min;
+use std::cmp::{max, min};
 #[cfg(test)]
 use std::collections::HashSet;
 use std::mem::size_of;
@@ -88,6 +88,12 @@ impl BuddyAllocator {
     // num_pages: u32
     // free_ends: array of u32, with ending offset for BtreeBitmap structure for the given order
     // ... BtreeBitmap structures
+    // Length of the result of `to_vec()`
+    pub(crate) fn serialized_len(&self) -> usize {
+        let data_len: usize = self.free.iter().map(BtreeBitmap::serialized_len).sum();
+        1 + 3 + size_of::<u32>() + (self.max_order as usize + 1) * size_of::<u32>() + data_len
+    }
+
     pub(crate) fn to_vec(&self) -> Vec<u8> {
         let serialized: Vec<Vec<u8>> = self.free.iter().map(BtreeBitmap::to_vec).collect();
 
@@ -162,6 +168,34 @@ impl BuddyAllocator {
         pages
     }
 
+    // Number of free blocks at each order
+    pub(crate) fn count_free_blocks_by_order(&self) -> Vec<u32> {
+        (0..=self.max_order)
+            .map(|order| self.get_order_free(order).count_unset())
+            .collect()
+    }
+
+    // Length of the longest run of contiguous free pages, which may span several free blocks
+    pub(crate) fn largest_free_run(&self) -> u32 {
+        let mut largest = 0;
+        let mut run = 0;
+        let mut page = 0;
+        while page < self.len() {
+            if let Some(order) = self.find_free_order(page) {
+                let order_size = 2u32.pow(order.into());
+                let block_end = page - page % order_size + order_size;
+                run += block_end - page;
+                page = block_end;
+            } else {
+                largest = max(largest, run);
+                run = 0;
+                page += 1;
+            }
+        }
+
+        max(largest, run)
+    }
+
     pub(crate) fn get_max_order(&self) -> u8 {
         self.max_order
     }
@@ -465,6 +499,7 @@ mod test {
             allocator.record_alloc(page, 0);
         }
         assert_eq!(allocator.count_allocated_pages(), num_pages);
+        assert_eq!(allocator.serialized_len(), allocator.to_vec().len());
 
         assert!(allocator.alloc(0).is_none());
 
@@ -517,6 +552,27 @@ mod test {
         assert_eq!(allocator.count_allocated_pages(), 0);
     }
 
+    #[test]
+    fn free_space_map() {
+        let num_pages = 256;
+        let mut allocator = BuddyAllocator::new(num_pages, num_pages);
+        assert_eq!(allocator.largest_free_run(), num_pages);
+
+        for page in [0, 3, 100] {
+            allocator.record_alloc(page, 0);
+        }
+        // Pages 101..256 are free, and span blocks of several orders
+        assert_eq!(allocator.largest_free_run(), 155);
+        assert_eq!(
+            allocator.count_free_blocks_by_order(),
+            vec![3, 1, 2, 2, 2, 2, 0, 1, 0]
+        );
+        assert_eq!(allocator.count_free_pages(), num_pages - 3);
+
+        allocator.record_alloc(150, 0);
+        assert_eq!(allocator.largest_free_run(), 105);
+    }
+
     #[test]
     fn serialized_size() {
         // Check that serialized size is as expected for a full region
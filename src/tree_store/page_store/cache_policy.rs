@@ -0,0 +1,208 @@
+use crate::tree_store::page_store::adaptive_cache::AdaptiveCache;
+use crate::tree_store::page_store::fast_hash::FastHashMapU64;
+use crate::tree_store::page_store::lru_cache::LRUCache;
+use crate::tree_store::page_store::two_queue_cache::TwoQueueCache;
+use std::collections::VecDeque;
+
+/// Policy used to choose which pages to evict from the read cache, when it is full
+///
+/// See [`crate::Builder::set_cache_policy`]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum CachePolicy {
+    /// Evict the least recently used page, approximated with a second-chance clock
+    ///
+    /// A scan that reads more data than fits in the cache will evict the whole working set.
+    #[default]
+    Lru,
+    /// 2Q. Newly read pages are placed in a probationary queue, and are only promoted to the main
+    /// queue if they are read again after being evicted from it
+    ///
+    /// Pages that are read only once, such as those read by a large scan, are evicted before the
+    /// working set.
+    TwoQueue,
+    /// Adaptive replacement (ARC), approximated with clocks (CAR). Pages are split between a
+    /// recency queue and a frequency queue, and the balance between the two adapts to the workload
+    ///
+    /// Pages that are read only once, such as those read by a large scan, are evicted before the
+    /// working set.
+    Adaptive,
+}
+
+enum PolicyCache<T> {
+    Lru(LRUCache<T>),
+    TwoQueue(TwoQueueCache<T>),
+    Adaptive(AdaptiveCache<T>),
+}
+
+// A read cache, using the eviction policy chosen by `CachePolicy`. Pinned entries are kept outside
+// of the policy, along with the id they were pinned on behalf of, and are never evicted
+pub(super) struct ReadCache<T> {
+    cache: PolicyCache<T>,
+    pinned: FastHashMapU64<(u64, T)>,
+}
+
+impl<T> ReadCache<T> {
+    pub(super) fn new(policy: CachePolicy) -> Self {
+        let cache = match policy {
+            CachePolicy::Lru => PolicyCache::Lru(LRUCache::new()),
+            CachePolicy::TwoQueue => PolicyCache::TwoQueue(TwoQueueCache::new()),
+            CachePolicy::Adaptive => PolicyCache::Adaptive(AdaptiveCache::new()),
+        };
+        Self {
+            cache,
+            pinned: FastHashMapU64::default(),
+        }
+    }
+
+    pub(super) fn insert(&mut self, key: u64, value: T) -> Option<T> {
+        if let Some((_, existing)) = self.pinned.get_mut(&key) {
+            return Some(std::mem::replace(existing, value));
+        }
+        match &mut self.cache {
+            PolicyCache::Lru(cache) => cache.insert(key, value),
+            PolicyCache::TwoQueue(cache) => cache.insert(key, value),
+            PolicyCache::Adaptive(cache) => cache.insert(key, value),
+        }
+    }
+
+    pub(super) fn insert_pinned(&mut self, key: u64, value: T, pin_id: u64) -> Option<T> {
+        let replaced = self.remove(key);
+        self.pinned.insert(key, (pin_id, value));
+        replaced
+    }
+
+    // Pins an entry which is already cached. Returns false if it is not cached
+    pub(super) fn pin(&mut self, key: u64, pin_id: u64) -> bool {
+        if self.pinned.contains_key(&key) {
+            return true;
+        }
+        if let Some(value) = self.remove(key) {
+            self.pinned.insert(key, (pin_id, value));
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(super) fn is_pinned(&self, key: u64) -> bool {
+        self.pinned.contains_key(&key)
+    }
+
+    // Returns the entries pinned on behalf of `pin_id` to the eviction policy
+    pub(super) fn unpin(&mut self, pin_id: u64) {
+        let keys: Vec<u64> = self
+            .pinned
+            .iter()
+            .filter(|(_, (id, _))| *id == pin_id)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in keys {
+            let (_, value) = self.pinned.remove(&key).unwrap();
+            self.insert(key, value);
+        }
+    }
+
+    // Returns all pinned entries to the eviction policy
+    pub(super) fn unpin_all(&mut self) {
+        let pinned = std::mem::take(&mut self.pinned);
+        for (key, (_, value)) in pinned {
+            self.insert(key, value);
+        }
+    }
+
+    pub(super) fn remove(&mut self, key: u64) -> Option<T> {
+        if let Some((_, value)) = self.pinned.remove(&key) {
+            return Some(value);
+        }
+        match &mut self.cache {
+            PolicyCache::Lru(cache) => cache.remove(key),
+            PolicyCache::TwoQueue(cache) => cache.remove(key),
+            PolicyCache::Adaptive(cache) => cache.remove(key),
+        }
+    }
+
+    pub(super) fn get(&self, key: u64) -> Option<&T> {
+        if let Some((_, value)) = self.pinned.get(&key) {
+            return Some(value);
+        }
+        match &self.cache {
+            PolicyCache::Lru(cache) => cache.get(key),
+            PolicyCache::TwoQueue(cache) => cache.get(key),
+            PolicyCache::Adaptive(cache) => cache.get(key),
+        }
+    }
+
+    pub(super) fn keys(&self) -> Vec<u64> {
+        let mut keys: Vec<u64> = match &self.cache {
+            PolicyCache::Lru(cache) => cache.iter().map(|(k, _)| *k).collect(),
+            PolicyCache::TwoQueue(cache) => cache.keys().collect(),
+            PolicyCache::Adaptive(cache) => cache.keys().collect(),
+        };
+        keys.extend(self.pinned.keys());
+        keys
+    }
+
+    // Pinned entries are never returned
+    pub(super) fn pop_lowest_priority(&mut self) -> Option<(u64, T)> {
+        match &mut self.cache {
+            PolicyCache::Lru(cache) => cache.pop_lowest_priority(),
+            PolicyCache::TwoQueue(cache) => cache.pop_lowest_priority(),
+            PolicyCache::Adaptive(cache) => cache.pop_lowest_priority(),
+        }
+    }
+}
+
+// Keys of recently evicted pages, without their data. Used by the scan resistant policies to
+// recognize pages which are read again soon after being evicted
+#[derive(Default)]
+pub(super) struct GhostList {
+    // Maps each key to the sequence number of its entry in `queue`. Entries in `queue` with a
+    // different sequence number are stale
+    keys: FastHashMapU64<u64>,
+    queue: VecDeque<(u64, u64)>,
+    next_sequence: u64,
+}
+
+impl GhostList {
+    pub(super) fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub(super) fn insert(&mut self, key: u64) {
+        self.keys.insert(key, self.next_sequence);
+        self.queue.push_back((key, self.next_sequence));
+        self.next_sequence += 1;
+        if self.queue.len() > 2 * self.keys.len() + 1 {
+            let keys = &self.keys;
+            self.queue
+                .retain(|(key, sequence)| keys.get(key) == Some(sequence));
+        }
+    }
+
+    pub(super) fn remove(&mut self, key: u64) -> bool {
+        self.keys.remove(&key).is_some()
+    }
+
+    pub(super) fn pop_oldest(&mut self) {
+        while let Some((key, sequence)) = self.queue.pop_front() {
+            if self.keys.get(&key) == Some(&sequence) {
+                self.keys.remove(&key);
+                return;
+            }
+        }
+    }
+}
+
+// Removes entries from `queue` for which `live` returns false, once more than half of them are
+// stale. Queue entries are (key, sequence number) pairs, so that an entry left behind by a page
+// which was removed and then inserted again can be told apart from the new one
+pub(super) fn compact_queue(
+    queue: &mut VecDeque<(u64, u64)>,
+    live_len: usize,
+    live: impl Fn(u64, u64) -> bool,
+) {
+    if queue.len() > 2 * live_len + 1 {
+        queue.retain(|(key, sequence)| live(*key, *sequence));
+    }
+}
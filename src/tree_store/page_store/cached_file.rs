@@ -1,11 +1,13 @@
+use crate::tree_store::btree_base::BRANCH;
 use crate::tree_store::page_store::base::PageHint;
+use crate::tree_store::page_store::cache_policy::{CachePolicy, ReadCache};
+use crate::tree_store::page_store::io_stats::TableIoCounters;
 use crate::tree_store::page_store::lru_cache::LRUCache;
 use crate::{CacheStats, DatabaseError, Result, StorageBackend, StorageError};
+use std::cmp::min;
 use std::ops::{Index, IndexMut};
 use std::slice::SliceIndex;
-#[cfg(feature = "cache_metrics")]
-use std::sync::atomic::AtomicU64;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, MutexGuard, RwLock};
 
 // Allocates an `Arc<[u8]>` in one step. `Arc::<[u8]>::from(vec![0; len])` would
@@ -120,6 +122,8 @@ struct CheckedBackend {
     file: Box<dyn StorageBackend>,
     io_failed: AtomicBool,
     closed: AtomicBool,
+    bytes_written: AtomicU64,
+    syncs: AtomicU64,
 }
 
 impl CheckedBackend {
@@ -128,6 +132,8 @@ impl CheckedBackend {
             file,
             io_failed: AtomicBool::new(false),
             closed: AtomicBool::new(false),
+            bytes_written: AtomicU64::new(0),
+            syncs: AtomicU64::new(0),
         }
     }
 
@@ -180,6 +186,7 @@ impl CheckedBackend {
 
     fn sync_data(&self) -> Result<()> {
         self.check_failure()?;
+        self.syncs.fetch_add(1, Ordering::Relaxed);
         let result = self.file.sync_data();
         if result.is_err() {
             self.io_failed.store(true, Ordering::Release);
@@ -189,6 +196,8 @@ impl CheckedBackend {
 
     fn write(&self, offset: u64, data: &[u8]) -> Result<()> {
         self.check_failure()?;
+        self.bytes_written
+            .fetch_add(data.len().try_into().unwrap(), Ordering::Relaxed);
         let result = self.file.write(offset, data);
         if result.is_err() {
             self.io_failed.store(true, Ordering::Release);
@@ -202,8 +211,10 @@ pub(super) struct PagedCachedFile {
     page_size: u64,
     // Dynamic cache partitioning.  Three invariants:
     //
-    // 1. The write buffer NEVER exceeds 50% of max_cache_size.
-    //    Pages beyond this limit are flushed to disk immediately.
+    // 1. The write buffer NEVER exceeds write_buffer_limit, which is at most 50% of
+    //    max_cache_size. Pages beyond this limit are flushed to disk immediately.
+    //    This is safe, because uncommitted pages are not reachable from the
+    //    committed roots.
     // 2. The write buffer evicts from the read cache only when
     //    write < 50% AND read > 50% (fairness).
     // 3. write + read never exceeds max_cache_size.
@@ -221,6 +232,7 @@ pub(super) struct PagedCachedFile {
     read_cache_bytes: AtomicUsize,
     write_buffer_bytes: AtomicUsize,
     max_cache_size: usize,
+    write_buffer_limit: AtomicUsize,
     // Rotates the starting stripe for read-cache eviction
     next_eviction_stripe: AtomicUsize,
     #[cfg(feature = "cache_metrics")]
@@ -233,7 +245,7 @@ pub(super) struct PagedCachedFile {
     writes_hits: AtomicU64,
     #[cfg(feature = "cache_metrics")]
     evictions: AtomicU64,
-    read_cache: Vec<RwLock<LRUCache<Arc<[u8]>>>>,
+    read_cache: Vec<RwLock<ReadCache<Arc<[u8]>>>>,
     // TODO: maybe move this cache to WriteTransaction?
     write_buffer: Arc<Mutex<LRUWriteCache>>,
 }
@@ -243,9 +255,10 @@ impl PagedCachedFile {
         file: Box<dyn StorageBackend>,
         page_size: u64,
         max_cache_size: usize,
+        cache_policy: CachePolicy,
     ) -> Result<Self, DatabaseError> {
         let read_cache = (0..Self::lock_stripes())
-            .map(|_| RwLock::new(LRUCache::new()))
+            .map(|_| RwLock::new(ReadCache::new(cache_policy)))
             .collect();
 
         Ok(Self {
@@ -254,6 +267,7 @@ impl PagedCachedFile {
             read_cache_bytes: AtomicUsize::new(0),
             write_buffer_bytes: AtomicUsize::new(0),
             max_cache_size,
+            write_buffer_limit: AtomicUsize::new(max_cache_size / 2),
             next_eviction_stripe: AtomicUsize::new(0),
             #[cfg(feature = "cache_metrics")]
             reads_total: AtomicU64::default(),
@@ -307,6 +321,24 @@ impl PagedCachedFile {
         self.file.close()
     }
 
+    // Limits the size of the write buffer. The limit is capped at half of the cache size
+    pub(super) fn set_write_buffer_limit(&self, limit: usize) {
+        self.write_buffer_limit
+            .store(min(limit, self.max_cache_size / 2), Ordering::Release);
+    }
+
+    pub(super) fn write_buffer_bytes(&self) -> usize {
+        self.write_buffer_bytes.load(Ordering::Acquire)
+    }
+
+    pub(super) fn bytes_written(&self) -> u64 {
+        self.file.bytes_written.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn syncs(&self) -> u64 {
+        self.file.syncs.load(Ordering::Relaxed)
+    }
+
     pub(crate) fn check_io_errors(&self) -> Result {
         self.file.check_failure()
     }
@@ -355,6 +387,13 @@ impl PagedCachedFile {
 
     fn flush_write_buffer(&self) -> Result {
         let mut write_buffer = self.write_buffer.lock().unwrap();
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "flush",
+            pages = write_buffer.cache.len(),
+            bytes = self.write_buffer_bytes.load(Ordering::Acquire),
+        )
+        .entered();
 
         for (offset, buffer) in write_buffer.cache.iter() {
             self.file.write(*offset, buffer.as_ref().unwrap())?;
@@ -405,8 +444,9 @@ impl PagedCachedFile {
         for cache_slot in 0..self.read_cache.len() {
             let mut lock = self.read_cache[cache_slot].write().unwrap();
             let stale: Vec<u64> = lock
-                .iter()
-                .filter_map(|(k, _)| (*k >= threshold).then_some(*k))
+                .keys()
+                .into_iter()
+                .filter(|k| *k >= threshold)
                 .collect();
             for k in stale {
                 if let Some(removed) = lock.remove(k) {
@@ -420,6 +460,8 @@ impl PagedCachedFile {
     pub(super) fn flush(&self) -> Result {
         self.flush_write_buffer()?;
 
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("fsync").entered();
         self.file.sync_data()
     }
 
@@ -456,17 +498,27 @@ impl PagedCachedFile {
 
     // Read with caching. Caller must not read overlapping ranges without first calling invalidate_cache().
     // Doing so will not cause UB, but is a logic error.
-    pub(super) fn read(&self, offset: u64, len: usize, hint: PageHint) -> Result<Arc<[u8]>> {
+    // Reads are recorded in `io_counters`, if provided
+    pub(super) fn read(
+        &self,
+        offset: u64,
+        len: usize,
+        hint: PageHint,
+        io_counters: Option<&TableIoCounters>,
+    ) -> Result<Arc<[u8]>> {
         debug_assert_eq!(0, offset % self.page_size);
         #[cfg(feature = "cache_metrics")]
         self.reads_total.fetch_add(1, Ordering::AcqRel);
 
-        if !matches!(hint, PageHint::Clean) {
+        if !hint.is_clean() {
             let lock = self.write_buffer.lock().unwrap();
             if let Some(cached) = lock.get(offset) {
                 #[cfg(feature = "cache_metrics")]
                 self.reads_hits.fetch_add(1, Ordering::Release);
                 debug_assert_eq!(cached.len(), len);
+                if let Some(counters) = io_counters {
+                    counters.record_read(true, len);
+                }
                 return Ok(cached.clone());
             }
         }
@@ -478,14 +530,40 @@ impl PagedCachedFile {
                 #[cfg(feature = "cache_metrics")]
                 self.reads_hits.fetch_add(1, Ordering::Release);
                 debug_assert_eq!(cached.len(), len);
-                return Ok(cached.clone());
+                if let Some(counters) = io_counters {
+                    counters.record_read(true, len);
+                }
+                let cached = cached.clone();
+                if let Some(pin_id) = hint.pin_id()
+                    && cached[0] == BRANCH
+                    && !read_lock.is_pinned(offset)
+                {
+                    drop(read_lock);
+                    self.read_cache[cache_slot]
+                        .write()
+                        .unwrap()
+                        .pin(offset, pin_id);
+                }
+                return Ok(cached);
             }
         }
 
         let buffer = self.read_direct_into_arc(offset, len)?;
+        if let Some(counters) = io_counters {
+            counters.record_read(false, len);
+        }
+        let pin_id = hint.pin_id().filter(|_| buffer[0] == BRANCH);
+        if !hint.fill_cache() && pin_id.is_none() {
+            return Ok(buffer);
+        }
         let cache_size = self.read_cache_bytes.fetch_add(len, Ordering::AcqRel);
         let mut write_lock = self.read_cache[cache_slot].write().unwrap();
-        let cache_size = if let Some(replaced) = write_lock.insert(offset, buffer.clone()) {
+        let replaced = if let Some(pin_id) = pin_id {
+            write_lock.insert_pinned(offset, buffer.clone(), pin_id)
+        } else {
+            write_lock.insert(offset, buffer.clone())
+        };
+        let cache_size = if let Some(replaced) = replaced {
             // A race could cause us to replace an existing buffer
             self.read_cache_bytes
                 .fetch_sub(replaced.len(), Ordering::AcqRel)
@@ -544,6 +622,7 @@ impl PagedCachedFile {
     pub(super) fn invalidate_cache_all(&self) {
         for cache_slot in 0..self.read_cache.len() {
             let mut lock = self.read_cache[cache_slot].write().unwrap();
+            lock.unpin_all();
             while let Some((_, removed)) = lock.pop_lowest_priority() {
                 self.read_cache_bytes
                     .fetch_sub(removed.len(), Ordering::AcqRel);
@@ -551,6 +630,13 @@ impl PagedCachedFile {
         }
     }
 
+    // Allow the pages pinned on behalf of `pin_id` to be evicted. They remain cached until then
+    pub(super) fn unpin(&self, pin_id: u64) {
+        for cache_slot in 0..self.read_cache.len() {
+            self.read_cache[cache_slot].write().unwrap().unpin(pin_id);
+        }
+    }
+
     // If overwrite is true, the page is initialized to zero
     // cache_policy takes the existing data as an argument and returns the priority. The priority should be stable and not change after WritablePage is dropped
     pub(super) fn write(&self, offset: u64, len: usize, overwrite: bool) -> Result<WritablePage> {
@@ -582,11 +668,11 @@ impl PagedCachedFile {
         } else {
             let previous = self.write_buffer_bytes.fetch_add(len, Ordering::AcqRel);
             let mut write_bytes = previous + len;
-            let half = self.max_cache_size / 2;
+            let limit = self.write_buffer_limit.load(Ordering::Acquire);
 
-            // Rule 1: write buffer NEVER exceeds 50%.  Flush excess to disk.
-            if write_bytes > half {
-                let excess = write_bytes - half;
+            // Rule 1: write buffer NEVER exceeds its limit.  Flush excess to disk.
+            if write_bytes > limit {
+                let excess = write_bytes - limit;
                 let mut flushed = 0;
                 while flushed < excess {
                     if let Some((offset, buffer)) = lock.pop_lowest_priority() {
@@ -620,7 +706,13 @@ impl PagedCachedFile {
             let result = if let Some(data) = existing {
                 #[cfg(feature = "cache_metrics")]
                 self.writes_hits.fetch_add(1, Ordering::AcqRel);
-                data
+                // A read transaction whose snapshot expired may still reference the page, so it
+                // must be copied rather than modified in place
+                if Arc::strong_count(&data) == 1 {
+                    data
+                } else {
+                    Arc::from(data.as_ref())
+                }
             } else if overwrite {
                 #[cfg(feature = "cache_metrics")]
                 self.writes_hits.fetch_add(1, Ordering::AcqRel);
@@ -646,6 +738,8 @@ mod test {
     use crate::StorageBackend;
     use crate::backends::InMemoryBackend;
     use crate::tree_store::PageHint;
+    use crate::tree_store::btree_base::BRANCH;
+    use crate::tree_store::page_store::cache_policy::CachePolicy;
     use crate::tree_store::page_store::cached_file::PagedCachedFile;
     use std::sync::Arc;
     use std::sync::atomic::Ordering;
@@ -654,14 +748,15 @@ mod test {
     fn cache_leak() {
         let backend = InMemoryBackend::new();
         backend.set_len(1024).unwrap();
-        let cached_file = PagedCachedFile::new(Box::new(backend), 128, 1024).unwrap();
+        let cached_file =
+            PagedCachedFile::new(Box::new(backend), 128, 1024, CachePolicy::Lru).unwrap();
         let cached_file = Arc::new(cached_file);
 
         let t1 = {
             let cached_file = cached_file.clone();
             std::thread::spawn(move || {
                 for _ in 0..1000 {
-                    cached_file.read(0, 128, PageHint::None).unwrap();
+                    cached_file.read(0, 128, PageHint::None, None).unwrap();
                     cached_file.invalidate_cache(0, 128);
                 }
             })
@@ -670,7 +765,7 @@ mod test {
             let cached_file = cached_file.clone();
             std::thread::spawn(move || {
                 for _ in 0..1000 {
-                    cached_file.read(0, 128, PageHint::None).unwrap();
+                    cached_file.read(0, 128, PageHint::None, None).unwrap();
                     cached_file.invalidate_cache(0, 128);
                 }
             })
@@ -682,15 +777,89 @@ mod test {
         assert_eq!(cached_file.read_cache_bytes.load(Ordering::Acquire), 0);
     }
 
+    #[test]
+    fn uncached_read() {
+        let backend = InMemoryBackend::new();
+        backend.set_len(1024).unwrap();
+        let cached_file =
+            PagedCachedFile::new(Box::new(backend), 128, 4096, CachePolicy::Lru).unwrap();
+
+        cached_file
+            .read(0, 128, PageHint::None.without_cache_fill(), None)
+            .unwrap();
+        cached_file
+            .read(128, 128, PageHint::Clean.without_cache_fill(), None)
+            .unwrap();
+        assert_eq!(cached_file.read_cache_bytes.load(Ordering::Acquire), 0);
+
+        // Pages which are already cached are still served from the cache
+        cached_file.read(0, 128, PageHint::None, None).unwrap();
+        assert_eq!(cached_file.read_cache_bytes.load(Ordering::Acquire), 128);
+        cached_file
+            .read(0, 128, PageHint::None.without_cache_fill(), None)
+            .unwrap();
+        assert_eq!(cached_file.read_cache_bytes.load(Ordering::Acquire), 128);
+    }
+
+    #[test]
+    fn pinned_branch_pages() {
+        // Offsets which map to the same lock stripe, and so evict each other
+        let stride = 128 * PagedCachedFile::lock_stripes();
+        let backend = InMemoryBackend::new();
+        backend.set_len(8 * stride).unwrap();
+        backend.write(0, &[BRANCH]).unwrap();
+        backend.write(stride, &[BRANCH]).unwrap();
+        let cached_file =
+            PagedCachedFile::new(Box::new(backend), 128, 512, CachePolicy::Lru).unwrap();
+        let is_pinned = |offset: u64| cached_file.read_cache[0].read().unwrap().is_pinned(offset);
+
+        // Pinned on a cache miss, and on a cache hit
+        cached_file
+            .read(0, 128, PageHint::CleanPinned(1), None)
+            .unwrap();
+        cached_file
+            .read(stride, 128, PageHint::Clean, None)
+            .unwrap();
+        cached_file
+            .read(stride, 128, PageHint::CleanPinned(2), None)
+            .unwrap();
+        // Only branch pages are pinned
+        cached_file
+            .read(2 * stride, 128, PageHint::CleanPinned(1), None)
+            .unwrap();
+        assert!(is_pinned(0));
+        assert!(is_pinned(stride));
+        assert!(!is_pinned(2 * stride));
+
+        for i in 3..8 {
+            cached_file
+                .read(i * stride, 128, PageHint::Clean, None)
+                .unwrap();
+        }
+        let read_cache = cached_file.read_cache[0].read().unwrap();
+        assert!(read_cache.get(0).is_some());
+        assert!(read_cache.get(stride).is_some());
+        assert!(read_cache.get(2 * stride).is_none());
+        drop(read_cache);
+
+        // Only the pages pinned on behalf of the given id are unpinned
+        cached_file.unpin(1);
+        assert!(!is_pinned(0));
+        assert!(is_pinned(stride));
+        cached_file.invalidate_cache_all();
+        assert_eq!(cached_file.read_cache_bytes.load(Ordering::Acquire), 0);
+    }
+
     #[test]
     fn resize_preserves_cached_pages() {
         let backend = InMemoryBackend::new();
         backend.set_len(1024).unwrap();
-        let cached_file = PagedCachedFile::new(Box::new(backend), 128, 4096).unwrap();
+        let cached_file =
+            PagedCachedFile::new(Box::new(backend), 128, 4096, CachePolicy::Lru).unwrap();
 
         // Populate the read cache with two pages from opposite ends of the file.
-        cached_file.read(0, 128, PageHint::None).unwrap();
-        cached_file.read(512, 128, PageHint::None).unwrap();
+        cached_file.read(0, 128, PageHint::None, None).unwrap();
+        cached_file.read(512, 128, PageHint::None, None).unwrap();
         assert_eq!(cached_file.read_cache_bytes.load(Ordering::Acquire), 256);
 
         // Growing must keep every cached page valid.
@@ -76,6 +76,21 @@ const TRANSACTION_LAST_FIELD: usize = TRANSACTION_ID_OFFSET + size_of::<u64>();
 const SLOT_CHECKSUM_OFFSET: usize = TRANSACTION_SIZE - size_of::<Checksum>();
 
 pub(crate) const PAGE_SIZE: usize = 4096;
+// Bounds on the configurable page size. The lower bound ensures that the super-header fits in a
+// single page.
+pub(crate) const MIN_PAGE_SIZE: usize = 512;
+pub(crate) const MAX_PAGE_SIZE: usize = 64 * 1024;
+
+// Returns the page size recorded in the header of an existing database, or None if `data` does not
+// start with a header, or the recorded page size is not supported
+pub(super) fn stored_page_size(data: &[u8]) -> Option<usize> {
+    if data.len() < REGION_HEADER_PAGES_OFFSET || data[..MAGICNUMBER.len()] != MAGICNUMBER {
+        return None;
+    }
+    let page_size = usize::try_from(get_u32(&data[PAGE_SIZE_OFFSET..])).ok()?;
+    (page_size.is_power_of_two() && (MIN_PAGE_SIZE..=MAX_PAGE_SIZE).contains(&page_size))
+        .then_some(page_size)
+}
 
 fn get_u32(data: &[u8]) -> u32 {
     u32::from_le_bytes(data[..size_of::<u32>()].try_into().unwrap())
@@ -0,0 +1,337 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+const HISTOGRAM_BUCKETS: usize = 65;
+
+/// A histogram with power-of-two buckets
+///
+/// Bucket `0` counts values equal to zero, and bucket `i` counts values in the range
+/// `2^(i-1)..2^i`
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    buckets: [u64; HISTOGRAM_BUCKETS],
+    sum: u64,
+}
+
+impl Histogram {
+    /// Number of recorded values
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// Sum of the recorded values
+    pub fn sum(&self) -> u64 {
+        self.sum
+    }
+
+    /// Returns an upper bound on the given quantile of the recorded values, or `None` if no values
+    /// have been recorded. `quantile` must be between 0.0 and 1.0
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    pub fn quantile(&self, quantile: f64) -> Option<u64> {
+        assert!((0.0..=1.0).contains(&quantile));
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = ((quantile * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            seen += bucket;
+            if seen >= rank {
+                return Some(Self::upper_bound(i));
+            }
+        }
+        unreachable!()
+    }
+
+    /// Returns the non-empty buckets, as pairs of the largest value in the bucket and the number
+    /// of recorded values in it
+    pub fn buckets(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(i, count)| (Self::upper_bound(i), *count))
+    }
+
+    fn upper_bound(bucket: usize) -> u64 {
+        if bucket == 0 {
+            0
+        } else {
+            u64::MAX >> (64 - bucket)
+        }
+    }
+}
+
+pub(crate) struct AtomicHistogram {
+    buckets: [AtomicU64; HISTOGRAM_BUCKETS],
+    sum: AtomicU64,
+}
+
+impl AtomicHistogram {
+    fn new() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            sum: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, value: u64) {
+        let bucket = (64 - value.leading_zeros()) as usize;
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(value, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> Histogram {
+        Histogram {
+            buckets: std::array::from_fn(|i| self.buckets[i].load(Ordering::Relaxed)),
+            sum: self.sum.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// I/O statistics for a single table, since the database was opened
+///
+/// Only collected when enabled with [`crate::Builder::set_table_io_stats`]
+#[derive(Clone, Debug, Default)]
+pub struct TableIoStats {
+    page_reads: u64,
+    cache_hits: u64,
+    cache_misses: u64,
+    bytes_read: u64,
+    bytes_written: u64,
+    pages_allocated: u64,
+    pages_freed: u64,
+}
+
+impl TableIoStats {
+    /// Number of pages read from the table, whether from the cache or from storage
+    pub fn page_reads(&self) -> u64 {
+        self.page_reads
+    }
+
+    /// Number of page reads which were served from the cache
+    pub fn cache_hits(&self) -> u64 {
+        self.cache_hits
+    }
+
+    /// Number of page reads which were not in the cache, and were read from storage
+    pub fn cache_misses(&self) -> u64 {
+        self.cache_misses
+    }
+
+    /// Number of bytes read from storage
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Number of bytes in the pages written by modifications to the table
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Number of pages allocated by modifications to the table
+    pub fn pages_allocated(&self) -> u64 {
+        self.pages_allocated
+    }
+
+    /// Number of pages freed by modifications to the table
+    pub fn pages_freed(&self) -> u64 {
+        self.pages_freed
+    }
+}
+
+/// A snapshot of the I/O statistics of a database, since it was opened
+///
+/// See [`crate::Database::io_stats`]
+#[derive(Clone, Debug)]
+pub struct IoStats {
+    commits: u64,
+    fsyncs: u64,
+    bytes_written: u64,
+    commit_latency_us: Histogram,
+    commit_bytes_written: Histogram,
+    tables: BTreeMap<String, TableIoStats>,
+}
+
+impl IoStats {
+    /// Number of committed write transactions
+    pub fn commits(&self) -> u64 {
+        self.commits
+    }
+
+    /// Number of times that storage was synced
+    pub fn fsyncs(&self) -> u64 {
+        self.fsyncs
+    }
+
+    /// Number of bytes written to storage
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Time taken by each commit, in microseconds
+    pub fn commit_latency_us(&self) -> &Histogram {
+        &self.commit_latency_us
+    }
+
+    /// Number of bytes written to storage by each write transaction, including its commit
+    pub fn commit_bytes_written(&self) -> &Histogram {
+        &self.commit_bytes_written
+    }
+
+    /// Statistics for the given table, if it has been opened since the database was opened, and
+    /// [`crate::Builder::set_table_io_stats`] is enabled
+    pub fn table(&self, name: &str) -> Option<&TableIoStats> {
+        self.tables.get(name)
+    }
+
+    /// Statistics for all the tables that have been opened since the database was opened, in
+    /// order of their names
+    pub fn tables(&self) -> impl Iterator<Item = (&str, &TableIoStats)> {
+        self.tables
+            .iter()
+            .map(|(name, stats)| (name.as_str(), stats))
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct TableIoCounters {
+    page_reads: AtomicU64,
+    cache_hits: AtomicU64,
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    pages_allocated: AtomicU64,
+    pages_freed: AtomicU64,
+}
+
+impl TableIoCounters {
+    pub(crate) fn record_read(&self, hit: bool, len: usize) {
+        self.page_reads.fetch_add(1, Ordering::Relaxed);
+        if hit {
+            self.cache_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.bytes_read
+                .fetch_add(len.try_into().unwrap(), Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn record_allocation(&self, len: usize) {
+        self.pages_allocated.fetch_add(1, Ordering::Relaxed);
+        self.bytes_written
+            .fetch_add(len.try_into().unwrap(), Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_free(&self) {
+        self.pages_freed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> TableIoStats {
+        let page_reads = self.page_reads.load(Ordering::Relaxed);
+        let cache_hits = self.cache_hits.load(Ordering::Relaxed);
+        TableIoStats {
+            page_reads,
+            cache_hits,
+            cache_misses: page_reads.saturating_sub(cache_hits),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            pages_allocated: self.pages_allocated.load(Ordering::Relaxed),
+            pages_freed: self.pages_freed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// Measures commit latency. `Instant` panics on wasm32-unknown-unknown, so nothing is measured there
+pub(crate) struct Stopwatch {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    start: std::time::Instant,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        {
+            self.start.elapsed()
+        }
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        {
+            Duration::ZERO
+        }
+    }
+}
+
+pub(crate) struct DatabaseIoCounters {
+    commits: AtomicU64,
+    commit_latency_us: AtomicHistogram,
+    commit_bytes_written: AtomicHistogram,
+}
+
+impl DatabaseIoCounters {
+    pub(crate) fn new() -> Self {
+        Self {
+            commits: AtomicU64::new(0),
+            commit_latency_us: AtomicHistogram::new(),
+            commit_bytes_written: AtomicHistogram::new(),
+        }
+    }
+
+    pub(crate) fn record_commit(&self, latency: Duration, bytes_written: u64) {
+        self.commits.fetch_add(1, Ordering::Relaxed);
+        self.commit_latency_us
+            .record(latency.as_micros().try_into().unwrap_or(u64::MAX));
+        self.commit_bytes_written.record(bytes_written);
+    }
+
+    pub(crate) fn snapshot<'a>(
+        &self,
+        fsyncs: u64,
+        bytes_written: u64,
+        tables: impl Iterator<Item = (&'a String, &'a TableIoCounters)>,
+    ) -> IoStats {
+        IoStats {
+            commits: self.commits.load(Ordering::Relaxed),
+            fsyncs,
+            bytes_written,
+            commit_latency_us: self.commit_latency_us.snapshot(),
+            commit_bytes_written: self.commit_bytes_written.snapshot(),
+            tables: tables
+                .map(|(name, counters)| (name.clone(), counters.snapshot()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tree_store::page_store::io_stats::AtomicHistogram;
+
+    #[test]
+    fn histogram() {
+        let histogram = AtomicHistogram::new();
+        assert_eq!(histogram.snapshot().quantile(0.5), None);
+        for value in [0, 1, 2, 3, 4, 100, u64::MAX] {
+            histogram.record(value);
+        }
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count(), 7);
+        assert_eq!(
+            snapshot.buckets().collect::<Vec<_>>(),
+            vec![(0, 1), (1, 1), (3, 2), (7, 1), (127, 1), (u64::MAX, 1)]
+        );
+        assert_eq!(snapshot.quantile(0.0), Some(0));
+        assert_eq!(snapshot.quantile(0.5), Some(3));
+        assert_eq!(snapshot.quantile(1.0), Some(u64::MAX));
+    }
+}
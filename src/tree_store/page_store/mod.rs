@@ -1,16 +1,21 @@
+mod adaptive_cache;
 mod backends;
 mod base;
 mod bitmap;
 mod buddy_allocator;
+mod cache_policy;
 mod cached_file;
 mod fast_hash;
 pub mod file_backend;
 mod header;
+mod io_stats;
 mod layout;
 mod lru_cache;
 mod page_manager;
 mod region;
 mod savepoint;
+mod space_stats;
+mod two_queue_cache;
 #[allow(clippy::pedantic, dead_code)]
 mod xxh3;
 
@@ -19,14 +24,18 @@ pub(crate) use backends::ReadOnlyBackend;
 pub(crate) use base::{
     MAX_PAIR_LENGTH, MAX_VALUE_LENGTH, Page, PageHint, PageNumber, PageTrackerPolicy,
 };
+pub use cache_policy::CachePolicy;
 pub(crate) use fast_hash::PageNumberHashSet;
-pub(crate) use header::PAGE_SIZE;
+pub(crate) use header::{MAX_PAGE_SIZE, MIN_PAGE_SIZE, PAGE_SIZE};
+pub(crate) use io_stats::Stopwatch;
+pub use io_stats::{Histogram, IoStats, TableIoStats};
 pub(crate) use page_manager::{
     AllocationPolicy, FILE_FORMAT_VERSION3, PageAllocator, PageResolver, ShrinkPolicy,
     TransactionalMemory, xxh3_checksum,
 };
 pub use savepoint::Savepoint;
 pub(crate) use savepoint::SerializedSavepoint;
+pub use space_stats::{RegionSpaceStats, SpaceStats};
 
 pub(super) use base::{PageImpl, PageMut};
 pub(super) use xxh3::hash128_with_seed;
@@ -1,31 +1,40 @@
 use crate::transaction_tracker::TransactionId;
-use crate::transactions::{AllocatorStateKey, AllocatorStateTree, AllocatorStateTreeMut};
+use crate::transactions::{
+    AllocatorStateKey, AllocatorStateTree, AllocatorStateTreeMut, PageList,
+    TransactionIdWithPagination,
+};
 use crate::tree_store::btree_base::{BtreeHeader, Checksum};
 use crate::tree_store::page_store::base::{MAX_PAGE_INDEX, PageHint};
 use crate::tree_store::page_store::buddy_allocator::BuddyAllocator;
+use crate::tree_store::page_store::cache_policy::CachePolicy;
 use crate::tree_store::page_store::cached_file::PagedCachedFile;
 use crate::tree_store::page_store::fast_hash::{PageNumberHashMap, PageNumberHashSet};
 use crate::tree_store::page_store::header::{
     DB_HEADER_SIZE, DatabaseHeader, MAGICNUMBER, TransactionHeader, UnrepairedDatabaseHeader,
+    stored_page_size,
 };
+use crate::tree_store::page_store::io_stats::{DatabaseIoCounters, IoStats, TableIoCounters};
 use crate::tree_store::page_store::layout::DatabaseLayout;
 use crate::tree_store::page_store::region::{Allocators, RegionTracker};
+use crate::tree_store::page_store::space_stats::RegionSpaceStats;
 use crate::tree_store::page_store::{PageImpl, PageMut, hash128_with_seed};
-use crate::tree_store::{Page, PageNumber, PageTrackerPolicy};
-use crate::{CacheStats, StorageBackend};
+use crate::tree_store::{Page, PageNumber, PageTrackerPolicy, RawLeafBuilder};
+use crate::{CacheStats, StorageBackend, Value};
 use crate::{DatabaseError, Result, StorageError};
 use std::cmp::{max, min};
 use std::collections::BTreeMap;
-#[cfg(debug_assertions)]
 use std::collections::HashMap;
 #[cfg(debug_assertions)]
 use std::collections::HashSet;
 use std::convert::TryInto;
 use std::io::ErrorKind;
+use std::iter::repeat_n;
 use std::marker::PhantomData;
 use std::sync::Arc;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
 use std::thread;
+use std::time::Duration;
 
 // The region header is optional in the v3 file format
 // It's an artifact of the v2 file format, so we initialize new databases without headers to save space
@@ -92,15 +101,46 @@ pub(crate) enum AllocationPolicy {
 #[derive(Clone)]
 pub(crate) struct PageResolver {
     mem: Arc<TransactionalMemory>,
+    io_counters: Option<Arc<TableIoCounters>>,
+    snapshot_expired: Option<Arc<AtomicBool>>,
 }
 
 impl PageResolver {
     pub(crate) fn new(mem: Arc<TransactionalMemory>) -> Self {
-        Self { mem }
+        Self {
+            mem,
+            io_counters: None,
+            snapshot_expired: None,
+        }
+    }
+
+    // Fails reads with `SnapshotExpired` once the given flag is set
+    pub(crate) fn with_snapshot_expiry(mut self, expired: Option<Arc<AtomicBool>>) -> Self {
+        self.snapshot_expired = expired;
+        self
+    }
+
+    // Records reads in the given table's I/O counters
+    pub(crate) fn with_io_counters(mut self, io_counters: Option<Arc<TableIoCounters>>) -> Self {
+        self.io_counters = io_counters;
+        self
     }
 
     pub(crate) fn get_page(&self, page_number: PageNumber, hint: PageHint) -> Result<PageImpl> {
-        self.mem.get_page(page_number, hint)
+        let page = self
+            .mem
+            .get_page(page_number, hint, self.io_counters.as_deref())?;
+        // Checked after the read, so that a page which was reused after the snapshot was released
+        // is never returned
+        if self
+            .snapshot_expired
+            .as_ref()
+            .is_some_and(|expired| expired.load(Ordering::Acquire))
+        {
+            return Err(StorageError::SnapshotExpired);
+        }
+
+        Ok(page)
     }
 
     pub(crate) fn count_allocated_pages(&self) -> Result<u64> {
@@ -108,6 +148,15 @@ impl PageResolver {
     }
 }
 
+// The number of pages that a transaction's commit will record as allocated or freed, and the number
+// of pages freed by earlier transactions that it will free before writing most of its metadata.
+// These determine how much room is held back for the metadata below the file size limit
+#[derive(Copy, Clone)]
+pub(crate) struct PendingCommit {
+    pages: u64,
+    freeable_pages: u64,
+}
+
 /// Per-write-transaction handle through which btree mutation code allocates
 /// and frees pages. Bundles the shared `TransactionalMemory` with the write
 /// transaction's `AllocationPolicy`.
@@ -116,6 +165,17 @@ pub(crate) struct PageAllocator {
     mem: Arc<TransactionalMemory>,
     policy: AllocationPolicy,
     allocated_since_commit: Arc<Mutex<PageTrackerPolicy>>,
+    // Cleared once the transaction starts committing, so that commit can use the space held back
+    // below the file size limit for its metadata
+    capacity_checks: Arc<AtomicBool>,
+    // Set when an allocation fails because of the file size limit, which may leave a modification
+    // half done
+    limit_exceeded: Arc<AtomicBool>,
+    // Pages freed by earlier transactions, which commit frees before it writes most of its metadata
+    freeable_pages: Arc<AtomicU64>,
+    // Number of pages passed to `defer_free()`, which commit records as freed
+    deferred_frees: Arc<AtomicU64>,
+    io_counters: Option<Arc<TableIoCounters>>,
 }
 
 impl PageAllocator {
@@ -124,12 +184,111 @@ impl PageAllocator {
             mem,
             policy,
             allocated_since_commit: Arc::new(Mutex::new(PageTrackerPolicy::new_tracking())),
+            capacity_checks: Arc::new(AtomicBool::new(true)),
+            limit_exceeded: Arc::new(AtomicBool::new(false)),
+            freeable_pages: Arc::new(AtomicU64::new(0)),
+            deferred_frees: Arc::new(AtomicU64::new(0)),
+            io_counters: None,
+        }
+    }
+
+    /// Returns a clone of this allocator, which records reads, allocations and frees in the given
+    /// table's I/O counters
+    pub(crate) fn with_io_counters(&self, io_counters: Option<Arc<TableIoCounters>>) -> Self {
+        Self {
+            io_counters,
+            ..self.clone()
+        }
+    }
+
+    /// Checks, before starting a modification, whether it is likely to fit within the file size
+    /// limit. `allocation_size` returns the largest single allocation it needs, and `pages` is the
+    /// number of additional single page allocations
+    pub(crate) fn ensure_capacity(
+        &self,
+        allocation_size: impl FnOnce() -> usize,
+        pages: usize,
+    ) -> Result {
+        let Some(pending_commit) = self.pending_commit() else {
+            return Ok(());
+        };
+        self.mem
+            .ensure_capacity(allocation_size, pages, pending_commit)
+    }
+
+    /// Sets the number of pages freed by earlier transactions, which commit will free before it
+    /// writes most of its metadata
+    pub(crate) fn set_freeable_pages(&self, pages: u64) {
+        self.freeable_pages.store(pages, Ordering::Release);
+    }
+
+    /// Lets allocations use the space held back for the metadata written by commit. Called when
+    /// the transaction begins committing
+    pub(crate) fn disable_capacity_checks(&self) {
+        self.capacity_checks.store(false, Ordering::Release);
+    }
+
+    /// Checks that the transaction can be committed within the file size limit, before commit
+    /// changes anything. `freed` is the number of pages it freed, `tables` the number of table
+    /// roots that commit updates, and `freeable` the pages freed by earlier transactions, which
+    /// commit frees
+    pub(crate) fn reserve_commit_space(
+        &self,
+        freed: u64,
+        tables: u64,
+        quick_repair: bool,
+        freeable: &[PageNumber],
+    ) -> Result {
+        // A modification may have been left incomplete by an allocation which failed
+        if self.limit_exceeded.load(Ordering::Acquire) {
+            return Err(StorageError::FileSizeLimitExceeded(
+                self.mem.max_file_len().unwrap(),
+            ));
+        }
+        self.mem.reserve_commit_space(
+            self.allocated_pages() + freed,
+            tables,
+            quick_repair,
+            freeable,
+        )
+    }
+
+    // Returns what determines how much space is held back for the metadata written by commit, or
+    // `None` once the transaction is committing
+    fn pending_commit(&self) -> Option<PendingCommit> {
+        if self.capacity_checks.load(Ordering::Acquire) {
+            Some(PendingCommit {
+                pages: self.allocated_pages() + self.deferred_frees.load(Ordering::Acquire),
+                freeable_pages: self.freeable_pages.load(Ordering::Acquire),
+            })
+        } else {
+            None
+        }
+    }
+
+    // Returns the number of pages that commit will record as allocated
+    fn allocated_pages(&self) -> u64 {
+        let allocated = self.allocated_since_commit.lock().unwrap().len();
+        u64::try_from(allocated + self.mem.unpersisted_len()).unwrap()
+    }
+
+    /// Prevents the transaction from being committed, if `result` failed because of the file size
+    /// limit. Used for the later steps of a modification, since it is incomplete if one of them
+    /// fails
+    pub(crate) fn check_limit<T>(&self, result: Result<T>) -> Result<T> {
+        result.inspect_err(|err| self.check_limit_error(err))
+    }
+
+    /// Like `check_limit()`, for an error which has already been extracted from its result
+    pub(crate) fn check_limit_error(&self, err: &StorageError) {
+        if matches!(err, StorageError::FileSizeLimitExceeded(_)) {
+            self.limit_exceeded.store(true, Ordering::Release);
         }
     }
 
     /// Returns a `PageResolver` for constructing read-only views of this transaction's pages.
     pub(crate) fn resolver(&self) -> PageResolver {
-        PageResolver::new(self.mem.clone())
+        PageResolver::new(self.mem.clone()).with_io_counters(self.io_counters.clone())
     }
 
     /// Drains the set of pages allocated since the last commit, returning
@@ -154,10 +313,12 @@ impl PageAllocator {
         size: usize,
         allocated: &mut PageTrackerPolicy,
     ) -> Result<PageMut<'a>> {
-        let page = match self.policy {
-            AllocationPolicy::Default => self.mem.allocate(size, allocated)?,
-            AllocationPolicy::Lowest => self.mem.allocate_lowest(size, allocated)?,
-        };
+        let pending_commit = self.pending_commit();
+        let page = self.check_limit(match self.policy {
+            AllocationPolicy::Default => self.mem.allocate(size, allocated, pending_commit),
+            AllocationPolicy::Lowest => self.mem.allocate_lowest(size, allocated, pending_commit),
+        })?;
+        self.record_allocation(&page);
         self.allocated_since_commit
             .lock()
             .unwrap()
@@ -173,7 +334,12 @@ impl PageAllocator {
         size: usize,
         allocated: &mut PageTrackerPolicy,
     ) -> Result<PageMut<'a>> {
-        let page = self.mem.allocate_lowest(size, allocated)?;
+        let page = self.check_limit(self.mem.allocate_lowest(
+            size,
+            allocated,
+            self.pending_commit(),
+        ))?;
+        self.record_allocation(&page);
         self.allocated_since_commit
             .lock()
             .unwrap()
@@ -181,7 +347,20 @@ impl PageAllocator {
         Ok(page)
     }
 
+    fn record_allocation(&self, page: &PageMut) {
+        if let Some(counters) = &self.io_counters {
+            counters.record_allocation(page.memory().len());
+        }
+    }
+
+    fn record_free(&self) {
+        if let Some(counters) = &self.io_counters {
+            counters.record_free();
+        }
+    }
+
     pub(crate) fn free(&self, page: PageNumber, allocated: &mut PageTrackerPolicy) {
+        self.record_free();
         self.allocated_since_commit
             .lock()
             .unwrap()
@@ -189,6 +368,13 @@ impl PageAllocator {
         self.mem.free(page, allocated);
     }
 
+    // Queues a committed page to be freed once the transaction commits
+    pub(crate) fn defer_free(&self, page: PageNumber, freed: &mut Vec<PageNumber>) {
+        self.record_free();
+        self.deferred_frees.fetch_add(1, Ordering::AcqRel);
+        freed.push(page);
+    }
+
     pub(crate) fn free_if_uncommitted(
         &self,
         page: PageNumber,
@@ -200,6 +386,7 @@ impl PageAllocator {
             .unwrap()
             .remove_if_present(page)
         {
+            self.record_free();
             self.mem.free(page, allocated);
             true
         } else {
@@ -212,7 +399,8 @@ impl PageAllocator {
     }
 
     pub(crate) fn get_page(&self, page_number: PageNumber, hint: PageHint) -> Result<PageImpl> {
-        self.mem.get_page(page_number, hint)
+        self.mem
+            .get_page(page_number, hint, self.io_counters.as_deref())
     }
 
     pub(crate) fn get_page_mut<'a>(&self, page_number: PageNumber) -> Result<PageMut<'a>> {
@@ -245,6 +433,10 @@ struct InMemoryState {
     // to readers until a durable commit promotes it to the primary slot on disk. Protected by the
     // enclosing Mutex so updates happen atomically with the header changes they describe.
     read_from_secondary: bool,
+    // The file is never shrunk below this length. Set by preallocation and reservations.
+    min_file_len: u64,
+    // Limit on the length of the file, which it is never grown past
+    max_file_len: Option<u64>,
 }
 
 impl InMemoryState {
@@ -253,6 +445,8 @@ impl InMemoryState {
             header,
             allocators: None,
             read_from_secondary: false,
+            min_file_len: 0,
+            max_file_len: None,
         }
     }
 
@@ -315,6 +509,15 @@ pub(crate) struct TransactionalMemory {
     // Reference counts of PageImpls that are outstanding
     #[cfg(debug_assertions)]
     read_page_ref_counts: Arc<Mutex<HashMap<PageNumber, u64>>>,
+    // Number of expired read transactions which have not been dropped yet. Pages they read may
+    // already have been freed and reused
+    #[cfg(debug_assertions)]
+    expired_read_transactions: AtomicU64,
+    // Pages which were referenced when a read transaction expired, or were read while an expired
+    // read transaction was live. The expired transaction may still reference them after they are
+    // freed, so they are not checked against `read_page_ref_counts` until they are unreferenced
+    #[cfg(debug_assertions)]
+    released_read_pages: Mutex<PageNumberHashSet>,
     // Set of all allocated pages for debugging assertions
     #[cfg(debug_assertions)]
     allocated_pages: Arc<Mutex<PageNumberHashSet>>,
@@ -323,9 +526,68 @@ pub(crate) struct TransactionalMemory {
     // code path where there is no locking
     region_size: u64,
     region_header_with_padding_size: u64,
+    // Minimum number of bytes by which the file is grown, when it runs out of space
+    growth_step: u64,
+    // Tables whose branch pages are pinned in the read cache, and the id they are pinned under
+    pinned_tables: RwLock<HashMap<String, u64>>,
+    next_pin_id: AtomicU64,
+    io_counters: DatabaseIoCounters,
+    // None, unless per-table I/O statistics are enabled
+    table_io_counters: Mutex<Option<HashMap<String, Arc<TableIoCounters>>>>,
+}
+
+// Smallest region which can hold the region tracker. It is saved as a single value in the
+// allocator state table, and a leaf can't span regions, so a smaller region fails to commit
+fn min_region_size(page_size: usize) -> u64 {
+    let key_len = AllocatorStateKey::fixed_width().unwrap();
+    let tracker_len = RegionTracker::new(INITIAL_REGIONS, MAX_MAX_PAGE_ORDER + 1)
+        .to_vec()
+        .len();
+    let leaf_len = RawLeafBuilder::required_bytes(1, key_len + tracker_len, Some(key_len), None);
+    (leaf_len.div_ceil(page_size).next_power_of_two() * page_size) as u64
+}
+
+// Pages which committing may allocate, besides the leaves of the page list tables, the table tree
+// and the allocator state: copies of the paths to the entries that it updates in the system tables
+const COMMIT_OVERHEAD_PAGES: usize = 32;
+
+// Returns the leaves that committing may add to the data page list tables, to record the given
+// number of allocated and freed pages, as pairs of the number of leaves and the number of pages
+// listed in each. An entry lists up to 400 pages, and each of the two tables may have a partial
+// entry. System pages are listed in entries of up to 200, so this counts full leaves for every 200
+fn page_list_leaves(pages: u64) -> [(usize, usize); 2] {
+    [
+        ((pages / 200).try_into().unwrap(), 400),
+        (2, min(pages, 400).try_into().unwrap()),
+    ]
+}
+
+// Size of a leaf holding a single entry of a page list table
+fn page_list_leaf_len(pages: usize) -> usize {
+    let key_len = TransactionIdWithPagination::fixed_width().unwrap();
+    RawLeafBuilder::required_bytes(
+        1,
+        key_len + PageList::required_bytes(pages),
+        Some(key_len),
+        None,
+    )
+}
+
+// Sizes of the leaves of the allocator state table, which a quick-repair commit writes
+fn allocator_state_leaf_lens(state: &InMemoryState) -> Vec<usize> {
+    let key_len = AllocatorStateKey::fixed_width().unwrap();
+    let allocators = state.allocators();
+    allocators
+        .region_allocators
+        .iter()
+        .map(BuddyAllocator::serialized_len)
+        .chain([allocators.region_tracker.serialized_len()])
+        .map(|len| RawLeafBuilder::required_bytes(1, key_len + len, Some(key_len), None))
+        .collect()
 }
 
 impl TransactionalMemory {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         file: Box<dyn StorageBackend>,
         // Allow initializing a new database in an empty file
@@ -333,8 +595,19 @@ impl TransactionalMemory {
         page_size: usize,
         requested_region_size: Option<u64>,
         cache_size: usize,
+        cache_policy: CachePolicy,
+        growth_step: u64,
         read_only: bool,
     ) -> Result<Self, DatabaseError> {
+        // The page size of an existing database is read from its header, so `page_size` is only
+        // used to initialize a new database
+        let page_size = if file.len().map_err(StorageError::from)? >= DB_HEADER_SIZE as u64 {
+            let mut header = [0; DB_HEADER_SIZE];
+            file.read(0, &mut header).map_err(StorageError::from)?;
+            stored_page_size(&header).unwrap_or(page_size)
+        } else {
+            page_size
+        };
         assert!(page_size.is_power_of_two() && page_size >= DB_HEADER_SIZE);
 
         let region_size = requested_region_size.unwrap_or(MAX_USABLE_REGION_SPACE);
@@ -342,9 +615,10 @@ impl TransactionalMemory {
             region_size,
             (u64::from(MAX_PAGE_INDEX) + 1) * page_size as u64,
         );
+        let region_size = max(region_size, min_region_size(page_size));
         assert!(region_size.is_power_of_two());
 
-        let storage = PagedCachedFile::new(file, page_size as u64, cache_size)?;
+        let storage = PagedCachedFile::new(file, page_size as u64, cache_size, cache_policy)?;
 
         let initial_storage_len = storage.raw_file_len()?;
 
@@ -457,10 +731,19 @@ impl TransactionalMemory {
             #[cfg(debug_assertions)]
             read_page_ref_counts: Arc::new(Mutex::new(HashMap::new())),
             #[cfg(debug_assertions)]
+            expired_read_transactions: AtomicU64::new(0),
+            #[cfg(debug_assertions)]
+            released_read_pages: Mutex::new(PageNumberHashSet::default()),
+            #[cfg(debug_assertions)]
             allocated_pages: Arc::new(Mutex::new(PageNumberHashSet::default())),
             page_size: page_size.try_into().unwrap(),
             region_size,
             region_header_with_padding_size: region_header_size,
+            growth_step,
+            pinned_tables: RwLock::new(HashMap::new()),
+            next_pin_id: AtomicU64::new(0),
+            io_counters: DatabaseIoCounters::new(),
+            table_io_counters: Mutex::new(None),
         })
     }
 
@@ -472,6 +755,49 @@ impl TransactionalMemory {
         self.storage.check_io_errors()
     }
 
+    pub(crate) fn set_table_io_stats(&self, enabled: bool) {
+        let mut counters = self.table_io_counters.lock().unwrap();
+        if enabled {
+            counters.get_or_insert_with(HashMap::new);
+        } else {
+            *counters = None;
+        }
+    }
+
+    // Returns the I/O counters for the given table, or None if per-table I/O statistics are disabled
+    pub(crate) fn table_io_counters(&self, name: &str) -> Option<Arc<TableIoCounters>> {
+        let mut counters = self.table_io_counters.lock().unwrap();
+        let counters = counters.as_mut()?;
+        if let Some(table) = counters.get(name) {
+            Some(table.clone())
+        } else {
+            let table = Arc::new(TableIoCounters::default());
+            counters.insert(name.to_string(), table.clone());
+            Some(table)
+        }
+    }
+
+    // Total number of bytes written to storage
+    pub(crate) fn bytes_written(&self) -> u64 {
+        self.storage.bytes_written()
+    }
+
+    pub(crate) fn record_commit(&self, latency: Duration, bytes_written: u64) {
+        self.io_counters.record_commit(latency, bytes_written);
+    }
+
+    pub(crate) fn io_stats(&self) -> IoStats {
+        let tables = self.table_io_counters.lock().unwrap();
+        self.io_counters.snapshot(
+            self.storage.syncs(),
+            self.storage.bytes_written(),
+            tables
+                .iter()
+                .flatten()
+                .map(|(name, counters)| (name, counters.as_ref())),
+        )
+    }
+
     // Panics in debug builds if any `PageMut` handed out by `get_page_mut` or
     // `allocate*` has not yet been dropped. Intended as a precondition for
     // commit/abort paths, which assume no mutable page references remain.
@@ -514,6 +840,29 @@ impl TransactionalMemory {
         }
     }
 
+    // Pages are pinned lazily, as they are read from tables with a hint from `table_page_hint()`
+    pub(crate) fn pin_table_branches(&self, name: &str) {
+        self.pinned_tables
+            .write()
+            .unwrap()
+            .entry(name.to_string())
+            .or_insert_with(|| self.next_pin_id.fetch_add(1, Ordering::Relaxed));
+    }
+
+    pub(crate) fn unpin_table_branches(&self, name: &str) {
+        if let Some(pin_id) = self.pinned_tables.write().unwrap().remove(name) {
+            self.storage.unpin(pin_id);
+        }
+    }
+
+    // Returns the hint for reading committed pages of the given table
+    pub(crate) fn table_page_hint(&self, name: &str) -> PageHint {
+        match self.pinned_tables.read().unwrap().get(name) {
+            Some(pin_id) => PageHint::CleanPinned(*pin_id),
+            None => PageHint::Clean,
+        }
+    }
+
     pub(crate) fn clear_read_cache(&self) {
         self.storage.invalidate_cache_all();
     }
@@ -784,7 +1133,11 @@ impl TransactionalMemory {
         let mut state = self.state.lock().unwrap();
         // Trim surplus file space, before finalizing the commit
         let shrunk = if !matches!(shrink_policy, ShrinkPolicy::Never) {
-            Self::try_shrink(&mut state, matches!(shrink_policy, ShrinkPolicy::Maximum))?
+            Self::try_shrink(
+                &mut state,
+                matches!(shrink_policy, ShrinkPolicy::Maximum),
+                self.growth_step,
+            )?
         } else {
             false
         };
@@ -866,7 +1219,13 @@ impl TransactionalMemory {
         Ok(())
     }
 
-    pub(crate) fn get_page(&self, page_number: PageNumber, hint: PageHint) -> Result<PageImpl> {
+    // Reads are recorded in `io_counters`, if provided
+    pub(crate) fn get_page(
+        &self,
+        page_number: PageNumber,
+        hint: PageHint,
+        io_counters: Option<&TableIoCounters>,
+    ) -> Result<PageImpl> {
         let range = page_number.address_range(
             self.page_size.into(),
             self.region_size,
@@ -874,19 +1233,28 @@ impl TransactionalMemory {
             self.page_size,
         );
         let len: usize = (range.end - range.start).try_into().unwrap();
-        let mem = self.storage.read(range.start, len, hint)?;
+        let mem = self.storage.read(range.start, len, hint, io_counters)?;
 
         // We must not retrieve an immutable reference to a page which already has a mutable ref to it
         #[cfg(debug_assertions)]
         {
             let dirty_pages = self.open_dirty_pages.lock().unwrap();
-            debug_assert!(!dirty_pages.contains(&page_number), "{page_number:?}");
-            *(self
-                .read_page_ref_counts
-                .lock()
-                .unwrap()
-                .entry(page_number)
-                .or_default()) += 1;
+            let expired_reader = self.expired_read_transactions.load(Ordering::Acquire) > 0;
+            debug_assert!(
+                expired_reader || !dirty_pages.contains(&page_number),
+                "{page_number:?}"
+            );
+            let mut ref_counts = self.read_page_ref_counts.lock().unwrap();
+            let ref_count = ref_counts.entry(page_number).or_default();
+            let mut released = self.released_read_pages.lock().unwrap();
+            if expired_reader {
+                released.insert(page_number);
+            } else if *ref_count == 0 {
+                released.remove(&page_number);
+            }
+            *ref_count += 1;
+            drop(released);
+            drop(ref_counts);
             drop(dirty_pages);
         }
 
@@ -898,17 +1266,44 @@ impl TransactionalMemory {
         })
     }
 
+    // Called when the snapshot of a read transaction is released before the transaction is dropped
+    pub(crate) fn read_snapshot_released(&self) {
+        #[cfg(debug_assertions)]
+        {
+            self.expired_read_transactions
+                .fetch_add(1, Ordering::AcqRel);
+            let ref_counts = self.read_page_ref_counts.lock().unwrap();
+            self.released_read_pages
+                .lock()
+                .unwrap()
+                .extend(ref_counts.keys());
+        }
+    }
+
+    // Called when a read transaction, whose snapshot was released, is dropped
+    #[cfg(debug_assertions)]
+    pub(crate) fn expired_read_transaction_dropped(&self) {
+        self.expired_read_transactions
+            .fetch_sub(1, Ordering::AcqRel);
+    }
+
+    #[cfg(debug_assertions)]
+    fn is_read_page_referenced(&self, page_number: PageNumber) -> bool {
+        let ref_counts = self.read_page_ref_counts.lock().unwrap();
+        let mut released = self.released_read_pages.lock().unwrap();
+        if ref_counts.contains_key(&page_number) {
+            !released.contains(&page_number)
+        } else {
+            released.remove(&page_number);
+            false
+        }
+    }
+
     // NOTE: the caller must ensure that the read cache has been invalidated or stale reads my occur
     pub(crate) fn get_page_mut<'txn>(&self, page_number: PageNumber) -> Result<PageMut<'txn>> {
         #[cfg(debug_assertions)]
         {
-            assert!(
-                !self
-                    .read_page_ref_counts
-                    .lock()
-                    .unwrap()
-                    .contains_key(&page_number)
-            );
+            assert!(!self.is_read_page_referenced(page_number));
             assert!(!self.open_dirty_pages.lock().unwrap().contains(&page_number));
         }
 
@@ -1026,13 +1421,7 @@ impl TransactionalMemory {
     fn free_helper(&self, page: PageNumber, allocated: &mut PageTrackerPolicy) {
         #[cfg(debug_assertions)]
         {
-            assert!(
-                !self
-                    .read_page_ref_counts
-                    .lock()
-                    .unwrap()
-                    .contains_key(&page)
-            );
+            assert!(!self.is_read_page_referenced(page));
             assert!(self.allocated_pages.lock().unwrap().remove(&page));
             assert!(!self.open_dirty_pages.lock().unwrap().contains(&page));
         }
@@ -1124,34 +1513,47 @@ impl TransactionalMemory {
         self.unpersisted.lock().unwrap().contains(&page)
     }
 
-    pub(crate) fn allocate_helper<'txn>(
+    // `pending_commit` determines the room held back for the metadata of the transaction's commit
+    // below the file size limit, and is `None` if the allocation is made by commit
+    fn allocate_helper<'txn>(
         &self,
         allocation_size: usize,
         lowest: bool,
+        pending_commit: Option<PendingCommit>,
     ) -> Result<PageMut<'txn>> {
         let required_pages = allocation_size.div_ceil(self.get_page_size());
         let required_order = ceil_log2(required_pages);
 
         let mut state = self.state.lock().unwrap();
+        // An allocation can't span regions, so it must fit within a single region
+        if u64::try_from(required_pages).unwrap()
+            > u64::from(state.header.layout().full_region_layout().num_pages())
+        {
+            return Err(StorageError::ValueTooLarge(allocation_size));
+        }
 
         let page_number = if let Some(page_number) =
             Self::allocate_helper_retry(&mut state, required_order, lowest)?
         {
             page_number
         } else {
-            self.grow(&mut state, required_order)?;
-            Self::allocate_helper_retry(&mut state, required_order, lowest)?.unwrap()
+            // Growth is capped by the file size limit, so it may take more than one step to make
+            // room for the allocation
+            loop {
+                self.grow(&mut state, required_order, pending_commit)?;
+                if let Some(page_number) =
+                    Self::allocate_helper_retry(&mut state, required_order, lowest)?
+                {
+                    break page_number;
+                }
+            }
         };
 
         #[cfg(debug_assertions)]
         {
             assert!(self.allocated_pages.lock().unwrap().insert(page_number));
             assert!(
-                !self
-                    .read_page_ref_counts
-                    .lock()
-                    .unwrap()
-                    .contains_key(&page_number),
+                !self.is_read_page_referenced(page_number),
                 "Allocated a page that is still referenced! {page_number:?}"
             );
             assert!(!self.open_dirty_pages.lock().unwrap().contains(&page_number));
@@ -1218,8 +1620,9 @@ impl TransactionalMemory {
         }
     }
 
-    fn try_shrink(state: &mut InMemoryState, force: bool) -> Result<bool> {
+    fn try_shrink(state: &mut InMemoryState, force: bool, growth_step: u64) -> Result<bool> {
         let layout = state.header.layout();
+        let page_size = u64::from(state.header.page_size());
         let last_region_index = layout.num_regions() - 1;
         let last_allocator = state.get_region(last_region_index);
         let trailing_free = last_allocator.trailing_free_pages();
@@ -1230,7 +1633,7 @@ impl TransactionalMemory {
         if trailing_free < last_allocator_len / 2 && !force {
             return Ok(false);
         }
-        let reduce_by = if layout.num_regions() > 1 && trailing_free == last_allocator_len {
+        let mut reduce_by = if layout.num_regions() > 1 && trailing_free == last_allocator_len {
             trailing_free
         } else if force {
             // Do not shrink the database to zero size
@@ -1238,6 +1641,23 @@ impl TransactionalMemory {
         } else {
             trailing_free / 2
         };
+        // Leave one growth step of free space, so that the file isn't immediately grown again
+        if !force {
+            let keep_free: u32 = (growth_step / page_size).try_into().unwrap_or(u32::MAX);
+            reduce_by = min(reduce_by, trailing_free.saturating_sub(keep_free));
+        }
+        // Never shrink below the preallocated length
+        let max_reduce: u32 = (layout.len().saturating_sub(state.min_file_len) / page_size)
+            .try_into()
+            .unwrap_or(u32::MAX);
+        if reduce_by > max_reduce {
+            // Keep at least one page in the last region, so that the region header pages (if any)
+            // are not removed along with it
+            reduce_by = min(max_reduce, last_allocator_len - 1);
+        }
+        if reduce_by == 0 {
+            return Ok(false);
+        }
 
         let mut new_layout = layout;
         new_layout.reduce_last_region(reduce_by);
@@ -1248,7 +1668,15 @@ impl TransactionalMemory {
         Ok(true)
     }
 
-    fn grow(&self, state: &mut InMemoryState, required_order_allocation: u8) -> Result<()> {
+    // Grows the file to make room for an allocation of the given order. The file never grows past
+    // its size limit, and unless the allocation is made by commit, room for the commit's metadata is
+    // held back below the limit. See `allocate_helper()`
+    fn grow(
+        &self,
+        state: &mut InMemoryState,
+        required_order_allocation: u8,
+        pending_commit: Option<PendingCommit>,
+    ) -> Result<()> {
         let layout = state.header.layout();
         let required_growth =
             2u64.pow(required_order_allocation.into()) * u64::from(state.header.page_size());
@@ -1273,16 +1701,55 @@ impl TransactionalMemory {
                 layout.usable_bytes() + required_growth * 2,
             )
         };
-        let new_layout = DatabaseLayout::calculate(
-            next_desired_size,
-            state.header.layout().full_region_layout().num_pages(),
-            state
-                .header
-                .layout()
-                .full_region_layout()
-                .get_header_pages(),
+        let mut next_desired_size =
+            max(next_desired_size, layout.usable_bytes() + self.growth_step);
+        if let Some(max_len) = state.max_file_len {
+            let reserve = pending_commit.map_or(0, |pending| self.commit_reserve(state, pending));
+            let max_usable_bytes = self.max_usable_bytes(layout, max_len.saturating_sub(reserve));
+            if max_usable_bytes < layout.usable_bytes() + required_growth {
+                return Err(StorageError::FileSizeLimitExceeded(max_len));
+            }
+            next_desired_size = min(next_desired_size, max_usable_bytes);
+        }
+        self.grow_to(state, next_desired_size)
+    }
+
+    fn layout_with_usable_bytes(
+        &self,
+        layout: DatabaseLayout,
+        usable_bytes: u64,
+    ) -> DatabaseLayout {
+        DatabaseLayout::calculate(
+            usable_bytes,
+            layout.full_region_layout().num_pages(),
+            layout.full_region_layout().get_header_pages(),
             self.page_size,
-        );
+        )
+    }
+
+    // Returns the largest number of usable bytes, that the file can be grown to without exceeding
+    // `max_len`. Never less than the current number of usable bytes
+    fn max_usable_bytes(&self, layout: DatabaseLayout, max_len: u64) -> u64 {
+        let overhead = layout.len() - layout.usable_bytes();
+        let mut usable_bytes = max_len.saturating_sub(overhead);
+        // Adding regions adds region headers, so reduce the estimate until it fits
+        loop {
+            if usable_bytes <= layout.usable_bytes() {
+                return layout.usable_bytes();
+            }
+            let new_layout = self.layout_with_usable_bytes(layout, usable_bytes);
+            if new_layout.len() <= max_len {
+                return usable_bytes;
+            }
+            let excess = new_layout.len() - max_len;
+            usable_bytes =
+                usable_bytes.saturating_sub(excess.next_multiple_of(self.page_size.into()));
+        }
+    }
+
+    fn grow_to(&self, state: &mut InMemoryState, desired_usable_bytes: u64) -> Result<()> {
+        let layout = state.header.layout();
+        let new_layout = self.layout_with_usable_bytes(layout, desired_usable_bytes);
         assert!(new_layout.len() >= layout.len());
 
         self.storage.resize(new_layout.len())?;
@@ -1299,12 +1766,285 @@ impl TransactionalMemory {
         Ok(())
     }
 
+    // Grows the file to at least `len` bytes, and prevents it from being shrunk below that length.
+    // Returns true if the file was grown. The new layout is only persisted by the next commit.
+    pub(crate) fn set_min_file_len(&self, len: u64) -> Result<bool> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(max_len) = state.max_file_len
+            && len > max_len
+        {
+            return Err(StorageError::FileSizeLimitExceeded(max_len));
+        }
+        state.min_file_len = max(state.min_file_len, len);
+        let layout = state.header.layout();
+        if layout.len() >= len {
+            return Ok(false);
+        }
+        self.grow_to(&mut state, layout.usable_bytes() + (len - layout.len()))?;
+        Ok(true)
+    }
+
+    // Limits the file to `len` bytes. Allocations which would grow it past the limit fail with
+    // `FileSizeLimitExceeded`
+    pub(crate) fn set_max_file_len(&self, len: Option<u64>) {
+        self.state.lock().unwrap().max_file_len = len;
+    }
+
+    // Limits the bytes of dirty pages buffered in memory. Pages beyond the budget are written to
+    // the file early
+    pub(crate) fn set_dirty_page_budget(&self, budget: Option<usize>) {
+        if let Some(budget) = budget {
+            self.storage.set_write_buffer_limit(budget);
+        }
+    }
+
+    pub(crate) fn dirty_bytes(&self) -> usize {
+        self.storage.write_buffer_bytes()
+    }
+
+    // Returns the sizes of the allocations which a commit may make, before and after it frees the
+    // pages which earlier transactions freed. `pages` is the number of allocated and freed pages
+    // that it records, `tables` the number of table roots that it updates, and `allocator_states`
+    // the number of copies of the allocator state to make room for
+    fn commit_allocations(
+        &self,
+        state: &InMemoryState,
+        pages: u64,
+        tables: u64,
+        allocator_states: usize,
+    ) -> [Vec<usize>; 2] {
+        let page_size = self.get_page_size();
+        // The table tree may need a leaf and a branch page for each table
+        let mut before = vec![page_size; 2 * usize::try_from(tables).unwrap()];
+        for (leaves, listed_pages) in page_list_leaves(pages) {
+            before.extend(repeat_n(page_list_leaf_len(listed_pages), leaves));
+            // Each leaf may also need a branch page to reference it
+            before.extend(repeat_n(page_size, leaves));
+        }
+
+        let state_leaves = allocator_state_leaf_lens(state);
+        let mut after = vec![];
+        for _ in 0..allocator_states {
+            after.extend(&state_leaves);
+            after.extend(repeat_n(page_size, state_leaves.len()));
+        }
+        // The pages which commit frees from the system tables are listed in an entry of up to 200
+        after.extend([page_list_leaf_len(200), page_size]);
+        after.extend(repeat_n(page_size, COMMIT_OVERHEAD_PAGES));
+        [before, after]
+    }
+
+    // Returns the bytes held back below the file size limit for the metadata written by a commit.
+    // This includes room for the allocator state, so that the quick-repair commit made when the
+    // database is closed succeeds. Room for it is held back twice, since a quick-repair commit
+    // leaves the state in the file, and the next commit must still fit
+    fn commit_reserve(&self, state: &InMemoryState, pending: PendingCommit) -> u64 {
+        let page_size = u64::from(self.page_size);
+        let block_bytes = |sizes: Vec<usize>| -> u64 {
+            sizes
+                .into_iter()
+                .map(|size| size.div_ceil(self.get_page_size()).next_power_of_two() as u64)
+                .sum::<u64>()
+                * page_size
+        };
+        let [before, after] = self.commit_allocations(state, pending.pages, 0, 2);
+        let before = block_bytes(before);
+        let after = block_bytes(after);
+        // The pages which commit frees can only be used for the allocations after it frees them
+        max(
+            before,
+            (before + after).saturating_sub(pending.freeable_pages * page_size),
+        )
+    }
+
+    pub(crate) fn max_file_len(&self) -> Option<u64> {
+        self.state.lock().unwrap().max_file_len
+    }
+
+    pub(crate) fn unpersisted_len(&self) -> usize {
+        self.unpersisted.lock().unwrap().len()
+    }
+
+    // Checks that the allocations which a commit may make fit within the file size limit, by making
+    // them and freeing them again, so that the commit can't fail partway through for lack of space.
+    // `pages` is the number of allocated and freed pages that it records, `tables` the number of
+    // table roots that it updates, and `freeable` the pages freed by earlier transactions, which it
+    // frees before it makes most of its allocations
+    pub(crate) fn reserve_commit_space(
+        &self,
+        pages: u64,
+        tables: u64,
+        quick_repair: bool,
+        freeable: &[PageNumber],
+    ) -> Result {
+        let [before, after] = {
+            let state = self.state.lock().unwrap();
+            if state.max_file_len.is_none() {
+                return Ok(());
+            }
+            self.commit_allocations(&state, pages, tables, usize::from(quick_repair))
+        };
+
+        let mut reserved = vec![];
+        let mut result = self.reserve_allocations(before, &mut reserved);
+        let freed = result.is_ok();
+        if freed {
+            self.set_free(freeable, true);
+            result = self.reserve_allocations(after, &mut reserved);
+        }
+        for page in reserved {
+            self.free_helper(page, &mut PageTrackerPolicy::Ignore);
+        }
+        if freed {
+            self.set_free(freeable, false);
+        }
+        result
+    }
+
+    // Allocates blocks of the given sizes, and adds them to `reserved`
+    fn reserve_allocations(&self, mut sizes: Vec<usize>, reserved: &mut Vec<PageNumber>) -> Result {
+        // Largest first, so that the smaller allocations don't fragment the space for them
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        for size in sizes {
+            reserved.push(self.allocate_helper(size, false, None)?.get_page_number());
+        }
+        Ok(())
+    }
+
+    // Marks allocated pages as free in the allocators, or free ones as allocated again, without
+    // the rest of the bookkeeping of allocating and freeing them
+    fn set_free(&self, pages: &[PageNumber], free: bool) {
+        let mut state = self.state.lock().unwrap();
+        for page in pages {
+            #[cfg(debug_assertions)]
+            {
+                let mut allocated_pages = self.allocated_pages.lock().unwrap();
+                if free {
+                    assert!(allocated_pages.remove(page));
+                } else {
+                    assert!(allocated_pages.insert(*page));
+                }
+            }
+            if free {
+                state
+                    .get_region_mut(page.region)
+                    .free(page.page_index, page.page_order);
+                state
+                    .get_region_tracker_mut()
+                    .mark_free(page.page_order, page.region);
+                // The page may be allocated with a different order, so drop it from the cache, as
+                // freeing it does
+                let address_range = page.address_range(
+                    self.page_size.into(),
+                    self.region_size,
+                    self.region_header_with_padding_size,
+                    self.page_size,
+                );
+                let len: usize = (address_range.end - address_range.start)
+                    .try_into()
+                    .unwrap();
+                self.storage.invalidate_cache(address_range.start, len);
+            } else {
+                state
+                    .get_region_mut(page.region)
+                    .record_alloc(page.page_index, page.page_order);
+            }
+        }
+    }
+
+    // Estimates whether an allocation of `allocation_size()` bytes and `pages` single page
+    // allocations fit within the file size limit, while leaving room for the metadata of the
+    // transaction's commit. Nothing is allocated: this only checks that there is a
+    // free block for the allocation, and enough free pages in total, counting the room to grow the
+    // file. It lets modifications fail before they start, but doesn't guarantee that they succeed,
+    // since allocations may fragment the free space
+    pub(crate) fn ensure_capacity(
+        &self,
+        allocation_size: impl FnOnce() -> usize,
+        pages: usize,
+        pending_commit: PendingCommit,
+    ) -> Result {
+        let mut state = self.state.lock().unwrap();
+        let Some(max_len) = state.max_file_len else {
+            return Ok(());
+        };
+        let allocation_size = allocation_size();
+        let layout = state.header.layout();
+        let page_size = u64::from(self.page_size);
+        let allocation_pages = max(allocation_size.div_ceil(self.get_page_size()), 1);
+        if u64::try_from(allocation_pages).unwrap()
+            > u64::from(layout.full_region_layout().num_pages())
+        {
+            return Err(StorageError::ValueTooLarge(allocation_size));
+        }
+        let allocation_order = ceil_log2(allocation_pages);
+        let block_pages = 1u64 << allocation_order;
+        let required_pages = block_pages + u64::try_from(pages).unwrap();
+
+        let reserve = self.commit_reserve(&state, pending_commit);
+        // Room to grow the file, without using the space held back for commit
+        let headroom_pages = (self.max_usable_bytes(layout, max_len.saturating_sub(reserve))
+            - layout.usable_bytes())
+            / page_size;
+        if headroom_pages >= required_pages {
+            return Ok(());
+        }
+        if headroom_pages < block_pages && !Self::has_free_block(&mut state, allocation_order) {
+            return Err(StorageError::FileSizeLimitExceeded(max_len));
+        }
+        // Otherwise free pages are used, and enough space must be left over for commit. Only count
+        // them when close to the limit, since that scans every region
+        let free_pages: u64 = state
+            .allocators()
+            .region_allocators
+            .iter()
+            .map(|allocator| u64::from(allocator.count_free_pages()))
+            .sum();
+        let max_headroom_pages =
+            (self.max_usable_bytes(layout, max_len) - layout.usable_bytes()) / page_size;
+        if max_headroom_pages + free_pages >= required_pages + reserve / page_size {
+            Ok(())
+        } else {
+            Err(StorageError::FileSizeLimitExceeded(max_len))
+        }
+    }
+
+    // Returns true if a block of the given order could be allocated without growing the file
+    fn has_free_block(state: &mut InMemoryState, order: u8) -> bool {
+        while let Some(candidate_region) = state.get_region_tracker_mut().find_free(order) {
+            if state
+                .get_region(candidate_region)
+                .highest_free_order()
+                .is_some_and(|free_order| free_order >= order)
+            {
+                return true;
+            }
+            // Mark the region as full, just like a failed allocation does
+            state
+                .get_region_tracker_mut()
+                .mark_full(order, candidate_region);
+        }
+        false
+    }
+
+    // Returns the file length that is needed for at least `bytes` of free space to be available
+    pub(crate) fn file_len_with_free_space(&self, bytes: u64) -> Result<u64> {
+        let state = self.state.lock().unwrap();
+        let mut free_pages = 0u64;
+        for i in 0..state.header.layout().num_regions() {
+            free_pages += u64::from(state.get_region(i).count_free_pages());
+        }
+        let free_bytes = free_pages * u64::from(self.page_size);
+        Ok(state.header.layout().len() + bytes.saturating_sub(free_bytes))
+    }
+
     fn allocate<'txn>(
         &self,
         allocation_size: usize,
         allocated: &mut PageTrackerPolicy,
+        pending_commit: Option<PendingCommit>,
     ) -> Result<PageMut<'txn>> {
-        let result = self.allocate_helper(allocation_size, false);
+        let result = self.allocate_helper(allocation_size, false, pending_commit);
         if let Ok(ref page) = result {
             allocated.insert(page.get_page_number());
         }
@@ -1315,8 +2055,9 @@ impl TransactionalMemory {
         &self,
         allocation_size: usize,
         allocated: &mut PageTrackerPolicy,
+        pending_commit: Option<PendingCommit>,
     ) -> Result<PageMut<'txn>> {
-        let result = self.allocate_helper(allocation_size, true);
+        let result = self.allocate_helper(allocation_size, true, pending_commit);
         if let Ok(ref page) = result {
             allocated.insert(page.get_page_number());
         }
@@ -1343,6 +2084,52 @@ impl TransactionalMemory {
         Ok(count)
     }
 
+    pub(crate) fn region_space_stats(&self) -> Vec<RegionSpaceStats> {
+        let state = self.state.lock().unwrap();
+        (0..state.header.layout().num_regions())
+            .map(|i| {
+                let allocator = state.get_region(i);
+                RegionSpaceStats {
+                    pages: allocator.len().into(),
+                    allocated_pages: allocator.count_allocated_pages().into(),
+                    free_blocks_by_order: allocator
+                        .count_free_blocks_by_order()
+                        .into_iter()
+                        .map(u64::from)
+                        .collect(),
+                    largest_free_run: allocator.largest_free_run().into(),
+                }
+            })
+            .collect()
+    }
+
+    // Number of bytes at the end of the file which are free, and could be trimmed by a commit
+    // with `ShrinkPolicy::Maximum`, or a sequence of them
+    pub(crate) fn shrinkable_bytes(&self) -> u64 {
+        let state = self.state.lock().unwrap();
+        let layout = state.header.layout();
+        let mut pages = 0u64;
+        for i in (0..layout.num_regions()).rev() {
+            let allocator = state.get_region(i);
+            let trailing_free = allocator.trailing_free_pages();
+            if i > 0 && trailing_free == allocator.len() {
+                pages += u64::from(trailing_free);
+            } else {
+                // Do not shrink the database to zero size
+                pages += u64::from(min(allocator.len() - 1, trailing_free));
+                break;
+            }
+        }
+        min(
+            pages * u64::from(self.page_size),
+            layout.len().saturating_sub(state.min_file_len),
+        )
+    }
+
+    pub(crate) fn file_len(&self) -> u64 {
+        self.state.lock().unwrap().header.layout().len()
+    }
+
     pub(crate) fn get_page_size(&self) -> usize {
         self.page_size.try_into().unwrap()
     }
@@ -1372,7 +2159,8 @@ impl TransactionalMemory {
 #[cfg(test)]
 mod test {
     use crate::tree_store::page_store::page_manager::INITIAL_REGIONS;
-    use crate::{Database, TableDefinition};
+    use crate::tree_store::{MAX_PAGE_SIZE, MIN_PAGE_SIZE};
+    use crate::{Database, ReadableDatabase, ReadableTableMetadata, TableDefinition};
 
     // Test that the region tracker expansion code works, by adding more data than fits into the initial max regions
     #[test]
@@ -1406,6 +2194,59 @@ mod test {
         assert!(db.check_integrity().unwrap());
     }
 
+    // Exercise the B-tree, buddy allocator, and multi-region layout at every supported page size
+    #[test]
+    fn all_page_sizes() {
+        let table_definition: TableDefinition<u32, &[u8]> = TableDefinition::new("x");
+        let mut page_size = MIN_PAGE_SIZE;
+        while page_size <= MAX_PAGE_SIZE {
+            let tmpfile = crate::create_tempfile();
+            let region_size: u64 = (16 * page_size).try_into().unwrap();
+            // Values that need 1, 2, and 3 pages, so that allocations of several orders are made
+            let value =
+                |i: u32| vec![u8::try_from(i % 256).unwrap(); (i as usize % 3) * page_size + 1];
+
+            let db = Database::builder()
+                .set_region_size(region_size)
+                .set_page_size(page_size)
+                .create(tmpfile.path())
+                .unwrap();
+            let txn = db.begin_write().unwrap();
+            {
+                let mut table = txn.open_table(table_definition).unwrap();
+                for i in 0..200 {
+                    table.insert(&i, value(i).as_slice()).unwrap();
+                }
+            }
+            txn.commit().unwrap();
+            let txn = db.begin_write().unwrap();
+            {
+                let mut table = txn.open_table(table_definition).unwrap();
+                for i in (0..200).step_by(2) {
+                    table.remove(&i).unwrap();
+                }
+            }
+            txn.commit().unwrap();
+            drop(db);
+
+            let mut db = Database::builder()
+                .set_region_size(region_size)
+                .set_page_size(page_size)
+                .open(tmpfile.path())
+                .unwrap();
+            assert!(db.check_integrity().unwrap());
+            db.compact().unwrap();
+            let txn = db.begin_read().unwrap();
+            let table = txn.open_table(table_definition).unwrap();
+            assert_eq!(table.len().unwrap(), 100);
+            for i in (1..200).step_by(2) {
+                assert_eq!(table.get(&i).unwrap().unwrap().value(), value(i).as_slice());
+            }
+
+            page_size *= 2;
+        }
+    }
+
     // Make sure the database remains consistent after a panic
     #[test]
     #[cfg(panic = "unwind")]
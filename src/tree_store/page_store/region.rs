@@ -23,6 +23,16 @@ impl RegionTracker {
         }
     }
 
+    // Length of the result of `to_vec()`
+    pub(super) fn serialized_len(&self) -> usize {
+        let data_len: usize = self
+            .order_trackers
+            .iter()
+            .map(BtreeBitmap::serialized_len)
+            .sum();
+        size_of::<u32>() + self.order_trackers.len() * size_of::<u32>() + data_len
+    }
+
     // Format:
     // num_orders: u32 number of order allocators
     // allocator_lens: u32 length of each allocator
@@ -0,0 +1,99 @@
+/// Allocation state of a single region of the database file
+///
+/// The file is divided into regions, each of which has its own buddy allocator. Free space is
+/// tracked in blocks of `2^order` pages
+#[derive(Clone, Debug)]
+pub struct RegionSpaceStats {
+    pub(crate) pages: u64,
+    pub(crate) allocated_pages: u64,
+    pub(crate) free_blocks_by_order: Vec<u64>,
+    pub(crate) largest_free_run: u64,
+}
+
+impl RegionSpaceStats {
+    /// Number of pages in the region
+    pub fn pages(&self) -> u64 {
+        self.pages
+    }
+
+    /// Number of pages which are allocated
+    pub fn allocated_pages(&self) -> u64 {
+        self.allocated_pages
+    }
+
+    /// Number of pages which are free
+    pub fn free_pages(&self) -> u64 {
+        self.pages - self.allocated_pages
+    }
+
+    /// Number of free blocks of each order. Element `i` counts the free blocks of `2^i` pages
+    pub fn free_blocks_by_order(&self) -> &[u64] {
+        &self.free_blocks_by_order
+    }
+
+    /// Length, in pages, of the longest run of contiguous free pages
+    pub fn largest_free_run(&self) -> u64 {
+        self.largest_free_run
+    }
+}
+
+/// A map of the free space in the database file
+///
+/// See [`crate::WriteTransaction::space_stats`]
+#[derive(Clone, Debug)]
+pub struct SpaceStats {
+    pub(crate) file_len: u64,
+    pub(crate) page_size: usize,
+    pub(crate) regions: Vec<RegionSpaceStats>,
+    pub(crate) pending_free_pages: u64,
+    pub(crate) shrinkable_bytes: u64,
+}
+
+impl SpaceStats {
+    /// Length of the database file, in bytes
+    pub fn file_len(&self) -> u64 {
+        self.file_len
+    }
+
+    /// Number of bytes per page
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Allocation state of each region, in order of their position in the file
+    pub fn regions(&self) -> &[RegionSpaceStats] {
+        &self.regions
+    }
+
+    /// Number of pages which are allocated, across all regions
+    pub fn allocated_pages(&self) -> u64 {
+        self.regions.iter().map(|x| x.allocated_pages).sum()
+    }
+
+    /// Number of pages which are free, across all regions
+    pub fn free_pages(&self) -> u64 {
+        self.regions.iter().map(RegionSpaceStats::free_pages).sum()
+    }
+
+    /// Length, in pages, of the longest run of contiguous free pages in any region
+    pub fn largest_free_run(&self) -> u64 {
+        self.regions
+            .iter()
+            .map(|x| x.largest_free_run)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Number of pages which have been freed by committed transactions, but not yet returned to
+    /// the allocator. They are returned once no read transaction or savepoint references them,
+    /// and are counted as allocated until then
+    pub fn pending_free_pages(&self) -> u64 {
+        self.pending_free_pages
+    }
+
+    /// Number of bytes of free space at the end of the file, by which it can be shrunk without
+    /// relocating any pages
+    pub fn shrinkable_bytes(&self) -> u64 {
+        self.shrinkable_bytes
+    }
+}
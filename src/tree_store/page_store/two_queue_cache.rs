@@ -0,0 +1,204 @@
+use crate::tree_store::page_store::cache_policy::{GhostList, compact_queue};
+use crate::tree_store::page_store::fast_hash::FastHashMapU64;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+struct Entry<T> {
+    value: T,
+    // Sequence number of this entry's position in its queue
+    sequence: u64,
+    // True if the entry is in the main queue, rather than the probationary queue
+    main: bool,
+    // Second chance flag. Only used in the main queue
+    referenced: AtomicBool,
+}
+
+// The "full" 2Q algorithm, from "2Q: A Low Overhead High Performance Buffer Management Replacement
+// Algorithm" by Johnson & Shasha. The main queue uses a second-chance clock, rather than an LRU
+// list, so that hits only need a shared reference.
+//
+// There is no fixed capacity, since the caller evicts entries as needed to stay within its byte
+// budget, so the queue sizes are derived from the number of entries currently cached.
+#[derive(Default)]
+pub(super) struct TwoQueueCache<T> {
+    cache: FastHashMapU64<Entry<T>>,
+    // Pages which have been read once. FIFO
+    probation: VecDeque<(u64, u64)>,
+    probation_len: usize,
+    // Pages which were read again after being evicted from the probationary queue
+    main: VecDeque<(u64, u64)>,
+    // Keys recently evicted from the probationary queue
+    ghosts: GhostList,
+    next_sequence: u64,
+}
+
+impl<T> TwoQueueCache<T> {
+    // Fraction of the cached entries that the probationary queue may hold, before it is preferred
+    // for eviction
+    const PROBATION_DIVISOR: usize = 4;
+    // Fraction of the cached entries that are remembered in the ghost list
+    const GHOST_DIVISOR: usize = 2;
+
+    pub(super) fn new() -> Self {
+        Self {
+            cache: FastHashMapU64::default(),
+            probation: VecDeque::default(),
+            probation_len: 0,
+            main: VecDeque::default(),
+            ghosts: GhostList::default(),
+            next_sequence: 0,
+        }
+    }
+
+    fn is_live(&self, key: u64, sequence: u64, main: bool) -> bool {
+        self.cache
+            .get(&key)
+            .is_some_and(|entry| entry.sequence == sequence && entry.main == main)
+    }
+
+    pub(super) fn insert(&mut self, key: u64, value: T) -> Option<T> {
+        if let Some(entry) = self.cache.get_mut(&key) {
+            return Some(std::mem::replace(&mut entry.value, value));
+        }
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        let main = self.ghosts.remove(key);
+        if main {
+            self.main.push_back((key, sequence));
+        } else {
+            self.probation.push_back((key, sequence));
+            self.probation_len += 1;
+        }
+        self.cache.insert(
+            key,
+            Entry {
+                value,
+                sequence,
+                main,
+                referenced: AtomicBool::new(false),
+            },
+        );
+        None
+    }
+
+    pub(super) fn remove(&mut self, key: u64) -> Option<T> {
+        let entry = self.cache.remove(&key)?;
+        if entry.main {
+            let main_len = self.cache.len() - self.probation_len;
+            let cache = &self.cache;
+            compact_queue(&mut self.main, main_len, |key, sequence| {
+                cache
+                    .get(&key)
+                    .is_some_and(|entry| entry.sequence == sequence && entry.main)
+            });
+        } else {
+            self.probation_len -= 1;
+            let cache = &self.cache;
+            compact_queue(&mut self.probation, self.probation_len, |key, sequence| {
+                cache
+                    .get(&key)
+                    .is_some_and(|entry| entry.sequence == sequence && !entry.main)
+            });
+        }
+        Some(entry.value)
+    }
+
+    pub(super) fn get(&self, key: u64) -> Option<&T> {
+        let entry = self.cache.get(&key)?;
+        // Hits in the probationary queue are not counted, since they are typically correlated
+        // with the read that brought the page into the cache
+        if entry.main {
+            entry.referenced.store(true, Ordering::Release);
+        }
+        Some(&entry.value)
+    }
+
+    pub(super) fn keys(&self) -> impl ExactSizeIterator<Item = u64> + '_ {
+        self.cache.keys().copied()
+    }
+
+    fn evict(&mut self, key: u64) -> T {
+        let entry = self.cache.remove(&key).unwrap();
+        if !entry.main {
+            self.probation_len -= 1;
+            self.ghosts.insert(key);
+            while self.ghosts.len() > self.cache.len() / Self::GHOST_DIVISOR {
+                self.ghosts.pop_oldest();
+            }
+        }
+        entry.value
+    }
+
+    pub(super) fn pop_lowest_priority(&mut self) -> Option<(u64, T)> {
+        loop {
+            let main_len = self.cache.len() - self.probation_len;
+            if self.probation_len > 0
+                && (self.probation_len > self.cache.len() / Self::PROBATION_DIVISOR
+                    || main_len == 0)
+            {
+                let (key, sequence) = self.probation.pop_front().unwrap();
+                if self.is_live(key, sequence, false) {
+                    return Some((key, self.evict(key)));
+                }
+            } else {
+                let (key, sequence) = self.main.pop_front()?;
+                if !self.is_live(key, sequence, true) {
+                    continue;
+                }
+                if self.cache[&key]
+                    .referenced
+                    .compare_exchange(true, false, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok()
+                {
+                    self.main.push_back((key, sequence));
+                } else {
+                    return Some((key, self.evict(key)));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tree_store::page_store::two_queue_cache::TwoQueueCache;
+
+    #[test]
+    fn scan_resistance() {
+        let mut cache = TwoQueueCache::new();
+        let capacity = 100;
+        let read = |cache: &mut TwoQueueCache<u64>, key| {
+            if cache.get(key).is_none() {
+                cache.insert(key, key);
+                while cache.cache.len() > capacity {
+                    cache.pop_lowest_priority().unwrap();
+                }
+            }
+        };
+
+        // Read the working set, and then enough other pages to push it out of the probationary
+        // queue. Reading it again promotes it to the main queue
+        for key in (0..50).chain(1000..1100).chain(0..50) {
+            read(&mut cache, key);
+        }
+        for key in 0..50 {
+            assert!(cache.cache[&key].main);
+        }
+
+        // A long scan does not evict the working set
+        for key in 10_000..20_000 {
+            read(&mut cache, key);
+        }
+        for key in 0..50 {
+            assert_eq!(cache.get(key), Some(&key));
+        }
+        assert_eq!(cache.keys().len(), capacity);
+
+        for key in 0..50 {
+            assert_eq!(cache.remove(key), Some(key));
+        }
+        while cache.pop_lowest_priority().is_some() {}
+        assert_eq!(cache.keys().len(), 0);
+        assert_eq!(cache.probation_len, 0);
+    }
+}
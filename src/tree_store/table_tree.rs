@@ -306,6 +306,11 @@ impl TableTreeMut {
         self.allocated_pages.lock().unwrap().close();
     }
 
+    // Number of tables whose roots are updated by `flush_and_close()`
+    pub(crate) fn pending_update_count(&self) -> usize {
+        self.pending_table_updates.len()
+    }
+
     pub(crate) fn flush_and_close(
         &mut self,
     ) -> Result<(Option<BtreeHeader>, PageNumberHashSet, Vec<PageNumber>)> {
@@ -474,6 +479,8 @@ impl TableTreeMut {
     }
 
     pub(crate) fn finalize_dirty_checksums(&mut self) -> Result<Option<BtreeHeader>> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("finalize_checksums").entered();
         self.tree.finalize_dirty_checksums()
     }
 
@@ -543,12 +550,16 @@ impl TableTreeMut {
             if self.get_table_untyped(new_name, table_type)?.is_some() {
                 return Err(TableError::TableExists(new_name.to_string()));
             }
+            assert!(self.tree.remove(&name)?.is_some());
+            let result = self
+                .tree
+                .insert(&new_name, &definition)
+                .map(|old| old.is_none());
+            assert!(self.page_allocator.check_limit(result)?);
             if let Some(update) = self.pending_table_updates.remove(name) {
                 self.pending_table_updates
                     .insert(new_name.to_string(), update);
             }
-            assert!(self.tree.remove(&name)?.is_some());
-            assert!(self.tree.insert(&new_name, &definition)?.is_none());
         } else {
             return Err(TableError::TableDoesNotExist(name.to_string()));
         }
@@ -569,6 +580,9 @@ impl TableTreeMut {
                 pages.push(path.page_number());
                 Ok(())
             })?;
+            // Removed from the table tree first, since that may fail for lack of space
+            let found = self.tree.remove(&name)?.is_some();
+
             let mut freed_pages = self.freed_pages.lock().unwrap();
             let mut allocated_pages = self.allocated_pages.lock().unwrap();
             for page in pages {
@@ -584,7 +598,6 @@ impl TableTreeMut {
 
             self.pending_table_updates.remove(name);
 
-            let found = self.tree.remove(&name)?.is_some();
             return Ok(found);
         }
 
@@ -1,9 +1,15 @@
-use std::cmp::Ordering;
+use std::cmp::{Ordering, Reverse};
 use std::convert::TryInto;
 use std::fmt::Debug;
 use std::mem::size_of;
+#[cfg(feature = "bytemuck")]
+pub(crate) mod bytemuck;
 #[cfg(feature = "chrono_v0_4")]
 mod chrono_v0_4;
+#[cfg(feature = "rkyv")]
+pub(crate) mod rkyv;
+#[cfg(feature = "serde")]
+pub(crate) mod serde;
 #[cfg(feature = "uuid")]
 mod uuid;
 
@@ -157,6 +163,17 @@ pub trait Key: Value {
     fn compare(data1: &[u8], data2: &[u8]) -> Ordering;
 }
 
+/// Gives a type the name by which it is identified in a table, when it is stored with an adapter
+/// such as `SerdeValue`, `SerdeKey`, `PodCopyValue`, or `RkyvValue`
+///
+/// The name is part of the [`TypeName`] of the table's key or value type, so it must stay the same
+/// as long as the stored encoding does, even if the type is renamed or moved to another module
+pub trait StableTypeName {
+    /// It is recommended that this be prefixed with the crate name to minimize the chance of it
+    /// colliding with another type
+    const NAME: &'static str;
+}
+
 impl Value for () {
     type SelfType<'a>
         = ()
@@ -309,6 +326,46 @@ impl<T: Key> Key for Option<T> {
     }
 }
 
+impl<T: Value> Value for Reverse<T> {
+    type SelfType<'a>
+        = Reverse<T::SelfType<'a>>
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = T::AsBytes<'a>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        T::fixed_width()
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Reverse<T::SelfType<'a>>
+    where
+        Self: 'a,
+    {
+        Reverse(T::from_bytes(data))
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> T::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        T::as_bytes(&value.0)
+    }
+
+    fn type_name() -> TypeName {
+        // The encoding is the same as T, but the type name must differ, since the ordering does
+        TypeName::internal(&format!("Reverse<{}>", T::type_name().name()))
+    }
+}
+
+impl<T: Key> Key for Reverse<T> {
+    fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+        T::compare(data2, data1)
+    }
+}
+
 impl Value for &[u8] {
     type SelfType<'a>
         = &'a [u8]
@@ -651,6 +708,21 @@ macro_rules! le_impl {
     };
 }
 
+macro_rules! float_impl {
+    ($t:ty) => {
+        le_value!($t);
+
+        /// Keys are ordered by the IEEE 754 `totalOrder` predicate, as in `total_cmp()`. Negative
+        /// NaNs sort before negative infinity, positive NaNs sort after positive infinity, and
+        /// `-0.0` sorts before, and is a distinct key from, `0.0`
+        impl Key for $t {
+            fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+                Self::from_bytes(data1).total_cmp(&Self::from_bytes(data2))
+            }
+        }
+    };
+}
+
 le_impl!(u8);
 le_impl!(u16);
 le_impl!(u32);
@@ -661,5 +733,5 @@ le_impl!(i16);
 le_impl!(i32);
 le_impl!(i64);
 le_impl!(i128);
-le_value!(f32);
-le_value!(f64);
+float_impl!(f32);
+float_impl!(f64);
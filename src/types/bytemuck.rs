@@ -0,0 +1,111 @@
+use crate::{StableTypeName, TypeName, Value};
+use bytemuck::Pod;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::mem::size_of;
+
+/// A fixed width value type which stores any `T` that implements bytemuck's [`Pod`], as its raw
+/// in-memory bytes, and copies it when it is read
+///
+/// Reads return an owned `T`, not a reference into the page. For example, a table defined as
+/// `TableDefinition<u64, PodCopyValue<Point>>` has values of type `Point`. Leaf data is not
+/// aligned, so a reference to `T` could not be returned, and each read copies `size_of::<T>()`
+/// bytes out of the page.
+///
+/// The encoding is the native-endian representation of `T`, so a database which uses it can only
+/// be read on a machine with the same endianness.
+///
+/// The [`TypeName`] includes [`StableTypeName::NAME`] of `T`, so the same table may be opened
+/// after `T` is renamed or moved to another module, as long as its name is unchanged
+#[derive(Debug)]
+pub struct PodCopyValue<T>(PhantomData<T>);
+
+impl<T: Pod + Debug + StableTypeName> Value for PodCopyValue<T> {
+    type SelfType<'a>
+        = T
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = &'a [u8]
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        Some(size_of::<T>())
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> T
+    where
+        Self: 'a,
+    {
+        bytemuck::pod_read_unaligned(data)
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a T) -> &'a [u8]
+    where
+        Self: 'b,
+    {
+        bytemuck::bytes_of(value)
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::internal(&format!("PodCopyValue<{}>", T::NAME))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::bytemuck::PodCopyValue;
+    use crate::{Database, ReadableDatabase, StableTypeName, TableDefinition, Value};
+    use bytemuck::{Pod, Zeroable};
+    use tempfile::NamedTempFile;
+
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+    struct Point {
+        x: f64,
+        y: f64,
+        id: u32,
+        flags: u32,
+    }
+
+    impl StableTypeName for Point {
+        const NAME: &'static str = "redb::bytemuck_tests::Point";
+    }
+
+    fn point(i: u8) -> Point {
+        Point {
+            x: f64::from(i) / 2.0,
+            y: -f64::from(i),
+            id: u32::from(i),
+            flags: u32::MAX - u32::from(i),
+        }
+    }
+
+    #[test]
+    fn pod_value() {
+        assert_eq!(<PodCopyValue<Point>>::fixed_width(), Some(24));
+        assert_eq!(
+            <PodCopyValue<Point>>::type_name().name(),
+            "PodCopyValue<redb::bytemuck_tests::Point>"
+        );
+
+        let definition: TableDefinition<u8, PodCopyValue<Point>> = TableDefinition::new("points");
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(definition).unwrap();
+            // The values follow an odd number of one byte keys in the leaf, so they are not aligned
+            for i in 0..101u8 {
+                table.insert(i, &point(i)).unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(definition).unwrap();
+        for i in 0..101u8 {
+            assert_eq!(table.get(i).unwrap().unwrap().value(), point(i));
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use crate::{StableTypeName, TypeName, Value};
+use rkyv::api::high::{HighSerializer, HighValidator};
+use rkyv::bytecheck::CheckBytes;
+use rkyv::rancor::Error;
+use rkyv::ser::allocator::ArenaHandle;
+use rkyv::util::AlignedVec;
+use rkyv::{Archive, Archived, Serialize};
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+/// A value type which stores any `T` that implements rkyv's `Archive`, as its archive
+///
+/// For example, a table defined as `TableDefinition<u64, RkyvValue<MyStruct>>` has values of type
+/// [`RkyvArchive<MyStruct>`], which dereferences to `ArchivedMyStruct`. Archives are validated when
+/// they are read, so the archived type must implement `CheckBytes`, and `Debug` (e.g. with
+/// `#[rkyv(derive(Debug))]`).
+///
+/// Values are inserted as an [`RkyvArchive`], whose bytes are stored as they are, so an archive
+/// which was read from a table may be inserted again without being re-archived.
+///
+/// Leaf data is not aligned, so values are only read zero-copy when rkyv accepts them where they
+/// are stored: when their position in the page happens to meet the alignment of the archived type,
+/// or when rkyv's `unaligned` format feature is enabled. Otherwise they are copied into an aligned
+/// buffer when they are read. redb does not enable an rkyv format itself.
+///
+/// The [`TypeName`] includes [`StableTypeName::NAME`] of `T`, so the same table may be opened
+/// after `T` is renamed or moved to another module, as long as its name is unchanged
+#[derive(Debug)]
+pub struct RkyvValue<T>(PhantomData<T>);
+
+enum ArchiveBytes<'a> {
+    Borrowed(&'a [u8]),
+    Owned(AlignedVec),
+}
+
+/// A validated rkyv archive of a `T`, which dereferences to `Archived<T>`
+///
+/// This is the value type of [`RkyvValue<T>`]. It borrows the bytes it was created from when rkyv
+/// accepts them where they are, and otherwise holds an aligned copy of them
+pub struct RkyvArchive<'a, T> {
+    bytes: ArchiveBytes<'a>,
+    _type: PhantomData<T>,
+}
+
+impl<T> RkyvArchive<'static, T>
+where
+    T: Archive + for<'a> Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, Error>>,
+{
+    /// Archives the given value
+    pub fn new(value: &T) -> Self {
+        Self {
+            bytes: ArchiveBytes::Owned(rkyv::to_bytes::<Error>(value).unwrap()),
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> RkyvArchive<'a, T>
+where
+    T: Archive,
+    Archived<T>: for<'b> CheckBytes<HighValidator<'b, Error>>,
+{
+    /// Validates the archive in `data`, such as one returned by `rkyv::to_bytes()`
+    ///
+    /// `data` is borrowed if rkyv accepts it where it is, and is copied otherwise
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` does not contain a valid archive of a `T`
+    pub fn from_bytes(data: &'a [u8]) -> Self {
+        let bytes = if rkyv::access::<Archived<T>, Error>(data).is_ok() {
+            ArchiveBytes::Borrowed(data)
+        } else {
+            let mut aligned = AlignedVec::with_capacity(data.len());
+            aligned.extend_from_slice(data);
+            rkyv::access::<Archived<T>, Error>(&aligned).unwrap();
+            ArchiveBytes::Owned(aligned)
+        };
+        Self {
+            bytes,
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<T> RkyvArchive<'_, T> {
+    /// Returns the bytes of the archive
+    pub fn as_bytes(&self) -> &[u8] {
+        match &self.bytes {
+            ArchiveBytes::Borrowed(data) => data,
+            ArchiveBytes::Owned(data) => data,
+        }
+    }
+}
+
+impl<T: Archive> Deref for RkyvArchive<'_, T> {
+    type Target = Archived<T>;
+
+    fn deref(&self) -> &Archived<T> {
+        // SAFETY: the bytes were validated as an archive of a `T`, at the address they are at now,
+        // when this was created, and are never modified
+        unsafe { rkyv::access_unchecked::<Archived<T>>(self.as_bytes()) }
+    }
+}
+
+impl<T: Archive> Debug for RkyvArchive<'_, T>
+where
+    Archived<T>: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> Value for RkyvValue<T>
+where
+    T: Archive + Debug + StableTypeName + 'static,
+    Archived<T>: Debug + for<'a> CheckBytes<HighValidator<'a, Error>>,
+{
+    type SelfType<'a>
+        = RkyvArchive<'a, T>
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = &'a [u8]
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> RkyvArchive<'a, T>
+    where
+        Self: 'a,
+    {
+        RkyvArchive::from_bytes(data)
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a RkyvArchive<'b, T>) -> &'a [u8]
+    where
+        Self: 'b,
+    {
+        value.as_bytes()
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::internal(&format!("RkyvValue<{}>", T::NAME))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::rkyv::{ArchiveBytes, RkyvArchive, RkyvValue};
+    use crate::{Database, ReadableDatabase, ReadableTable, StableTypeName, TableDefinition};
+    use rkyv::{Archive, Deserialize, Serialize};
+    use tempfile::NamedTempFile;
+
+    #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+    #[rkyv(derive(Debug))]
+    struct Record {
+        id: u64,
+        name: String,
+        readings: Vec<f32>,
+        parent: Option<u32>,
+    }
+
+    impl StableTypeName for Record {
+        const NAME: &'static str = "redb::rkyv_tests::Record";
+    }
+
+    #[test]
+    fn rkyv_value() {
+        let definition: TableDefinition<&str, RkyvValue<Record>> = TableDefinition::new("records");
+        let copy_definition: TableDefinition<u32, RkyvValue<Record>> =
+            TableDefinition::new("copies");
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(definition).unwrap();
+            // Keys of varying length, so that the values are not aligned in the leaf
+            for i in 0..50u32 {
+                let record = Record {
+                    id: u64::from(i),
+                    name: format!("record {i}"),
+                    readings: vec![0.5; i as usize],
+                    parent: i.checked_sub(1),
+                };
+                let key = "k".repeat(i as usize + 1);
+                if i % 2 == 0 {
+                    table
+                        .insert(key.as_str(), RkyvArchive::new(&record))
+                        .unwrap();
+                } else {
+                    let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&record).unwrap();
+                    let archive = RkyvArchive::<Record>::from_bytes(&bytes);
+                    assert!(matches!(archive.bytes, ArchiveBytes::Borrowed(_)));
+                    table.insert(key.as_str(), archive).unwrap();
+
+                    // Misaligned archives are copied
+                    let mut shifted = vec![0];
+                    shifted.extend_from_slice(&bytes);
+                    let archive = RkyvArchive::<Record>::from_bytes(&shifted[1..]);
+                    assert!(matches!(archive.bytes, ArchiveBytes::Owned(_)));
+                    assert_eq!(archive.as_bytes(), bytes.as_slice());
+                    assert_eq!(archive.id, u64::from(i));
+                }
+            }
+        }
+        {
+            // Archives which were read are inserted again as they are
+            let table = write_txn.open_table(definition).unwrap();
+            let mut copies = write_txn.open_table(copy_definition).unwrap();
+            for i in 0..50u32 {
+                let key = "k".repeat(i as usize + 1);
+                let guard = table.get(key.as_str()).unwrap().unwrap();
+                copies.insert(i, guard.value()).unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(definition).unwrap();
+        let copies = read_txn.open_table(copy_definition).unwrap();
+        for i in 0..50u32 {
+            let key = "k".repeat(i as usize + 1);
+            let guard = table.get(key.as_str()).unwrap().unwrap();
+            let archived = guard.value();
+            assert_eq!(archived.id, u64::from(i));
+            assert_eq!(archived.name, format!("record {i}"));
+            assert_eq!(archived.readings.len(), i as usize);
+            assert_eq!(
+                archived.parent.as_ref().map(|x| x.to_native()),
+                i.checked_sub(1)
+            );
+            let record: Record =
+                rkyv::deserialize::<Record, rkyv::rancor::Error>(&*archived).unwrap();
+            assert_eq!(record.name, format!("record {i}"));
+            assert_eq!(
+                copies.get(i).unwrap().unwrap().value().as_bytes(),
+                archived.as_bytes()
+            );
+        }
+    }
+}
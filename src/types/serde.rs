@@ -0,0 +1,362 @@
+use crate::{Key, StableTypeName, TypeName, Value};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+/// A serialization format for [`SerdeValue`] and [`SerdeKey`]
+///
+/// Values are serialized when they are inserted, so a value which fails to deserialize indicates
+/// that the table is corrupted, or was written with a different type or codec. Implementations
+/// therefore panic on errors, rather than returning them
+pub trait SerdeCodec: Debug + 'static {
+    /// Name of the codec, which is included in the [`TypeName`] of the values encoded with it
+    fn name() -> &'static str;
+
+    /// Serializes the given value
+    fn serialize<T: Serialize>(value: &T) -> Vec<u8>;
+
+    /// Deserializes a value that was serialized by [`Self::serialize`]
+    fn deserialize<T: DeserializeOwned>(data: &[u8]) -> T;
+}
+
+/// A [`SerdeCodec`] whose encoding preserves order, so that comparing the encodings of two values
+/// byte-wise gives the same result as comparing the values themselves
+///
+/// Only implement this for codecs which guarantee that property for every type they support,
+/// since keys encoded with it are compared without being deserialized
+pub trait OrderPreservingCodec: SerdeCodec {}
+
+/// A value type which stores any `T` that implements serde's `Serialize` and `Deserialize`,
+/// encoded with the codec `C`
+///
+/// Values of `T` are used directly. For example, a table defined as
+/// `TableDefinition<&str, SerdeValue<MyStruct, PostcardCodec>>` has values of type `MyStruct`.
+///
+/// The [`TypeName`] includes the codec and [`StableTypeName::NAME`] of `T`, so the same table may
+/// be opened after `T` is renamed or moved to another module, as long as its name is unchanged
+#[derive(Debug)]
+pub struct SerdeValue<T, C>(PhantomData<(T, C)>);
+
+impl<T, C> Value for SerdeValue<T, C>
+where
+    T: Debug + Serialize + DeserializeOwned + StableTypeName + 'static,
+    C: SerdeCodec,
+{
+    type SelfType<'a>
+        = T
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> T
+    where
+        Self: 'a,
+    {
+        C::deserialize(data)
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a T) -> Vec<u8>
+    where
+        Self: 'b,
+    {
+        C::serialize(value)
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::internal(&format!("SerdeValue<{},{}>", T::NAME, C::name()))
+    }
+}
+
+/// A key type which stores any `T` that implements serde's `Serialize` and `Deserialize`,
+/// encoded with the order-preserving codec `C`. Keys are compared byte-wise, without being
+/// deserialized
+///
+/// See [`SerdeValue`] for how the [`TypeName`] is derived
+#[derive(Debug)]
+pub struct SerdeKey<T, C>(PhantomData<(T, C)>);
+
+impl<T, C> Value for SerdeKey<T, C>
+where
+    T: Debug + Serialize + DeserializeOwned + StableTypeName + 'static,
+    C: OrderPreservingCodec,
+{
+    type SelfType<'a>
+        = T
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> T
+    where
+        Self: 'a,
+    {
+        C::deserialize(data)
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a T) -> Vec<u8>
+    where
+        Self: 'b,
+    {
+        C::serialize(value)
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::internal(&format!("SerdeKey<{},{}>", T::NAME, C::name()))
+    }
+}
+
+impl<T, C> Key for SerdeKey<T, C>
+where
+    T: Debug + Serialize + DeserializeOwned + StableTypeName + 'static,
+    C: OrderPreservingCodec,
+{
+    fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+        data1.cmp(data2)
+    }
+}
+
+/// The [postcard](https://docs.rs/postcard) codec
+#[cfg(feature = "serde_postcard")]
+#[derive(Debug)]
+pub struct PostcardCodec;
+
+#[cfg(feature = "serde_postcard")]
+impl SerdeCodec for PostcardCodec {
+    fn name() -> &'static str {
+        "postcard"
+    }
+
+    fn serialize<T: Serialize>(value: &T) -> Vec<u8> {
+        postcard::to_allocvec(value).unwrap()
+    }
+
+    fn deserialize<T: DeserializeOwned>(data: &[u8]) -> T {
+        postcard::from_bytes(data).unwrap()
+    }
+}
+
+/// The [bincode](https://docs.rs/bincode) codec, with its standard configuration
+#[cfg(feature = "serde_bincode")]
+#[derive(Debug)]
+pub struct BincodeCodec;
+
+#[cfg(feature = "serde_bincode")]
+impl SerdeCodec for BincodeCodec {
+    fn name() -> &'static str {
+        "bincode"
+    }
+
+    fn serialize<T: Serialize>(value: &T) -> Vec<u8> {
+        bincode::serde::encode_to_vec(value, bincode::config::standard()).unwrap()
+    }
+
+    fn deserialize<T: DeserializeOwned>(data: &[u8]) -> T {
+        let (value, len) =
+            bincode::serde::decode_from_slice(data, bincode::config::standard()).unwrap();
+        assert_eq!(len, data.len());
+        value
+    }
+}
+
+/// The [CBOR](https://docs.rs/ciborium) codec
+#[cfg(feature = "serde_cbor")]
+#[derive(Debug)]
+pub struct CborCodec;
+
+#[cfg(feature = "serde_cbor")]
+impl SerdeCodec for CborCodec {
+    fn name() -> &'static str {
+        "cbor"
+    }
+
+    fn serialize<T: Serialize>(value: &T) -> Vec<u8> {
+        let mut result = vec![];
+        ciborium::into_writer(value, &mut result).unwrap();
+        result
+    }
+
+    fn deserialize<T: DeserializeOwned>(data: &[u8]) -> T {
+        ciborium::from_reader(data).unwrap()
+    }
+}
+
+/// The [JSON](https://docs.rs/serde_json) codec
+#[cfg(feature = "serde_json")]
+#[derive(Debug)]
+pub struct JsonCodec;
+
+#[cfg(feature = "serde_json")]
+impl SerdeCodec for JsonCodec {
+    fn name() -> &'static str {
+        "json"
+    }
+
+    fn serialize<T: Serialize>(value: &T) -> Vec<u8> {
+        serde_json::to_vec(value).unwrap()
+    }
+
+    fn deserialize<T: DeserializeOwned>(data: &[u8]) -> T {
+        serde_json::from_slice(data).unwrap()
+    }
+}
+
+/// The [storekey](https://docs.rs/storekey) codec, which is order-preserving
+#[cfg(feature = "serde_storekey")]
+#[derive(Debug)]
+pub struct StorekeyCodec;
+
+#[cfg(feature = "serde_storekey")]
+impl SerdeCodec for StorekeyCodec {
+    fn name() -> &'static str {
+        "storekey"
+    }
+
+    fn serialize<T: Serialize>(value: &T) -> Vec<u8> {
+        storekey::serialize(value).unwrap()
+    }
+
+    fn deserialize<T: DeserializeOwned>(data: &[u8]) -> T {
+        storekey::deserialize(data).unwrap()
+    }
+}
+
+#[cfg(feature = "serde_storekey")]
+impl OrderPreservingCodec for StorekeyCodec {}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::serde::{SerdeCodec, SerdeValue};
+    use crate::{Database, ReadableDatabase, StableTypeName, TableDefinition, Value};
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+    use tempfile::NamedTempFile;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Record {
+        name: String,
+        readings: Vec<f64>,
+        labels: BTreeMap<String, Option<u32>>,
+    }
+
+    impl StableTypeName for Record {
+        const NAME: &'static str = "redb::serde_tests::Record";
+    }
+
+    // Unused if no codecs are enabled
+    #[allow(dead_code)]
+    fn round_trip<C: SerdeCodec>() {
+        let definition: TableDefinition<u64, SerdeValue<Record, C>> =
+            TableDefinition::new("records");
+        let record = Record {
+            name: "sensor".to_string(),
+            readings: vec![1.5, -2.0],
+            labels: BTreeMap::from([("a".to_string(), Some(1)), ("b".to_string(), None)]),
+        };
+
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(definition).unwrap();
+            table.insert(0, &record).unwrap();
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(definition).unwrap();
+        assert_eq!(table.get(0).unwrap().unwrap().value(), record);
+        assert_eq!(
+            <SerdeValue<Record, C>>::type_name().name(),
+            format!("SerdeValue<redb::serde_tests::Record,{}>", C::name())
+        );
+    }
+
+    #[cfg(feature = "serde_postcard")]
+    #[test]
+    fn postcard() {
+        round_trip::<crate::PostcardCodec>();
+    }
+
+    #[cfg(feature = "serde_bincode")]
+    #[test]
+    fn bincode() {
+        round_trip::<crate::BincodeCodec>();
+    }
+
+    #[cfg(feature = "serde_cbor")]
+    #[test]
+    fn cbor() {
+        round_trip::<crate::CborCodec>();
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn json() {
+        round_trip::<crate::JsonCodec>();
+    }
+
+    #[cfg(feature = "serde_storekey")]
+    #[test]
+    fn storekey() {
+        use crate::{ReadableTable, SerdeKey, StorekeyCodec};
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone)]
+        struct Path {
+            user: i64,
+            segments: Vec<String>,
+        }
+
+        impl StableTypeName for Path {
+            const NAME: &'static str = "redb::serde_tests::Path";
+        }
+
+        round_trip::<StorekeyCodec>();
+
+        let definition: TableDefinition<SerdeKey<Path, StorekeyCodec>, u32> =
+            TableDefinition::new("paths");
+        let mut keys = vec![];
+        for user in [-5, 3, 0] {
+            for segments in [vec!["b"], vec![], vec!["a", "z"], vec!["a"]] {
+                keys.push(Path {
+                    user,
+                    segments: segments.into_iter().map(str::to_string).collect(),
+                });
+            }
+        }
+
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(definition).unwrap();
+            for key in &keys {
+                table.insert(key, &0).unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+
+        keys.sort();
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(definition).unwrap();
+        let stored: Vec<Path> = table
+            .iter()
+            .unwrap()
+            .map(|x| x.unwrap().0.value())
+            .collect();
+        assert_eq!(stored, keys);
+    }
+}
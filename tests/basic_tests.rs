@@ -5,11 +5,12 @@ use redb::CommitError;
 use redb::DatabaseError;
 use redb::backends::InMemoryBackend;
 use redb::{
-    Database, Key, MultimapTableDefinition, MultimapTableHandle, Range, ReadOnlyDatabase,
-    ReadableDatabase, ReadableTable, ReadableTableMetadata, TableDefinition, TableError,
-    TableHandle, TypeName, Value,
+    Database, Key, Memcomparable, MultimapTableDefinition, MultimapTableHandle, Range,
+    ReadOnlyDatabase, ReadOptions, ReadableDatabase, ReadableTable, ReadableTableMetadata,
+    TableDefinition, TableError, TableHandle, TypeName, Value,
 };
-use std::cmp::Ordering;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 #[cfg(not(target_os = "wasi"))]
 use std::sync;
 
@@ -2156,6 +2157,169 @@ fn f32_type() {
     assert_eq!(0.3, table.get(&0).unwrap().unwrap().value());
 }
 
+#[test]
+fn float_keys() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<f64, u32> = TableDefinition::new("x");
+    let tuple_definition: TableDefinition<(Option<f32>, f64), u32> = TableDefinition::new("y");
+
+    let keys = [
+        f64::NAN,
+        -0.0,
+        f64::NEG_INFINITY,
+        1.5,
+        0.0,
+        -f64::NAN,
+        f64::INFINITY,
+        -2.5,
+    ];
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        let mut tuple_table = write_txn.open_table(tuple_definition).unwrap();
+        for (i, key) in keys.iter().enumerate() {
+            table.insert(key, &u32::try_from(i).unwrap()).unwrap();
+            #[allow(clippy::cast_possible_truncation)]
+            tuple_table
+                .insert((Some(*key as f32), *key), &u32::try_from(i).unwrap())
+                .unwrap();
+        }
+        tuple_table.insert((None, 0.0), &100).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(table.len().unwrap(), 8);
+    // -0.0 and 0.0 are distinct keys
+    assert_eq!(table.get(&-0.0).unwrap().unwrap().value(), 1);
+    assert_eq!(table.get(&0.0).unwrap().unwrap().value(), 4);
+    assert_eq!(table.get(&f64::NAN).unwrap().unwrap().value(), 0);
+    let order: Vec<u32> = table
+        .iter()
+        .unwrap()
+        .map(|x| x.unwrap().1.value())
+        .collect();
+    assert_eq!(order, vec![5, 2, 7, 1, 4, 3, 6, 0]);
+    let range: Vec<u32> = table
+        .range(-1.0..f64::INFINITY)
+        .unwrap()
+        .map(|x| x.unwrap().1.value())
+        .collect();
+    assert_eq!(range, vec![1, 4, 3]);
+
+    let tuple_table = read_txn.open_table(tuple_definition).unwrap();
+    let order: Vec<u32> = tuple_table
+        .iter()
+        .unwrap()
+        .map(|x| x.unwrap().1.value())
+        .collect();
+    assert_eq!(order, vec![100, 5, 2, 7, 1, 4, 3, 6, 0]);
+}
+
+#[test]
+fn reverse_key() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<(u64, Reverse<u64>), &str> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for (user, timestamp, event) in [
+            (1, 100, "a"),
+            (2, 150, "b"),
+            (1, 300, "c"),
+            (1, 200, "d"),
+            (2, 50, "e"),
+        ] {
+            table.insert((user, Reverse(timestamp)), event).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(table.get((1, Reverse(200))).unwrap().unwrap().value(), "d");
+    // The latest two events for user 1, with a forward scan
+    let latest: Vec<(u64, String)> = table
+        .range((1, Reverse(u64::MAX))..=(1, Reverse(0)))
+        .unwrap()
+        .take(2)
+        .map(|x| {
+            let (key, value) = x.unwrap();
+            (key.value().1.0, value.value().to_string())
+        })
+        .collect();
+    assert_eq!(latest, vec![(300, "c".to_string()), (200, "d".to_string())]);
+    assert_eq!(table.first().unwrap().unwrap().0.value(), (1, Reverse(300)));
+    assert_eq!(table.last().unwrap().unwrap().0.value(), (2, Reverse(50)));
+
+    // Reverse<u64> is not compatible with u64, since the ordering differs
+    let write_txn = db.begin_write().unwrap();
+    let incompatible: TableDefinition<(u64, u64), &str> = TableDefinition::new("x");
+    assert!(matches!(
+        write_txn.open_table(incompatible),
+        Err(TableError::TableTypeMismatch { .. })
+    ));
+}
+
+#[test]
+fn memcomparable_key() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    type Tuple = (i32, String, Option<u16>, [bool; 2]);
+    let definition: TableDefinition<Memcomparable<Tuple>, u64> = TableDefinition::new("x");
+
+    let strings = ["", "\0", "\0\0", "a", "a\0", "a\0b", "ab", "b"];
+    let mut keys: Vec<Tuple> = vec![];
+    for i in 0..500 {
+        keys.push((
+            [i32::MIN, -1, 0, 1, i32::MAX][i % 5],
+            strings[random::<u32>() as usize % strings.len()].to_string(),
+            if random() { Some(random()) } else { None },
+            [random(), random()],
+        ));
+    }
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for key in &keys {
+            table.insert(key, &0).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    keys.sort();
+    keys.dedup();
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    let stored: Vec<Tuple> = table
+        .iter()
+        .unwrap()
+        .map(|x| x.unwrap().0.value())
+        .collect();
+    assert_eq!(stored, keys);
+    // The raw encodings are sorted in the same order
+    let encoded: Vec<Vec<u8>> = keys.iter().map(<Memcomparable<Tuple>>::as_bytes).collect();
+    assert!(encoded.windows(2).all(|x| x[0] < x[1]));
+
+    let floats = [f64::NEG_INFINITY, -1.5, -0.0, 0.0, 2.5, f64::INFINITY];
+    let encoded: Vec<Vec<u8>> = floats.iter().map(<Memcomparable<f64>>::as_bytes).collect();
+    assert!(encoded.windows(2).all(|x| x[0] < x[1]));
+    for (float, bytes) in floats.iter().zip(&encoded) {
+        assert_eq!(
+            <Memcomparable<f64>>::from_bytes(bytes).to_bits(),
+            float.to_bits()
+        );
+    }
+    assert_eq!(<Memcomparable<(u32, Option<u64>)>>::fixed_width(), Some(13));
+}
+
 #[test]
 fn str_type() {
     let tmpfile = create_tempfile();
@@ -2370,6 +2534,124 @@ fn vec_vec_type() {
     assert_eq!(value, table.get(0).unwrap().unwrap().value());
 }
 
+#[test]
+fn vec_key_type() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<Vec<&str>, u32> = TableDefinition::new("x");
+    let fixed_definition: TableDefinition<Vec<u16>, u32> = TableDefinition::new("y");
+
+    let long_str = "a".repeat(300);
+    let paths = [
+        vec!["usr", "lib"],
+        vec![],
+        vec!["usr"],
+        vec!["etc", "hosts"],
+        vec!["usr", long_str.as_str()],
+        vec!["usr", "bin", "redb"],
+        vec!["usr", "bin"],
+    ];
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for (i, path) in paths.iter().enumerate() {
+            table.insert(path, &u32::try_from(i).unwrap()).unwrap();
+        }
+        let mut table = write_txn.open_table(fixed_definition).unwrap();
+        for (i, key) in [vec![2, 1], vec![1, 300], vec![1], vec![300]]
+            .iter()
+            .enumerate()
+        {
+            table.insert(key, &u32::try_from(i).unwrap()).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(table.get(&vec!["usr", "bin"]).unwrap().unwrap().value(), 6);
+    let order: Vec<u32> = table
+        .iter()
+        .unwrap()
+        .map(|x| x.unwrap().1.value())
+        .collect();
+    assert_eq!(order, vec![1, 3, 2, 4, 6, 5, 0]);
+    // All the paths under "usr"
+    let range: Vec<u32> = table
+        .range(vec!["usr", ""]..vec!["usr\0"])
+        .unwrap()
+        .map(|x| x.unwrap().1.value())
+        .collect();
+    assert_eq!(range, vec![4, 6, 5, 0]);
+
+    let table = read_txn.open_table(fixed_definition).unwrap();
+    let order: Vec<u32> = table
+        .iter()
+        .unwrap()
+        .map(|x| x.unwrap().1.value())
+        .collect();
+    assert_eq!(order, vec![2, 1, 0, 3]);
+}
+
+#[test]
+fn map_and_set_value_types() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let btree_map_definition: TableDefinition<u8, BTreeMap<&str, Option<u64>>> =
+        TableDefinition::new("btree_map");
+    let hash_map_definition: TableDefinition<u8, HashMap<String, Vec<&str>>> =
+        TableDefinition::new("hash_map");
+    let btree_set_definition: TableDefinition<u8, BTreeSet<u32>> =
+        TableDefinition::new("btree_set");
+    let hash_set_definition: TableDefinition<u8, HashSet<&str>> = TableDefinition::new("hash_set");
+
+    let btree_map = BTreeMap::from([("color", Some(3)), ("size", None), ("", Some(1))]);
+    let hash_map = HashMap::from([
+        ("b".to_string(), vec!["x", "y"]),
+        ("a".to_string(), vec![]),
+        ("c".repeat(300), vec!["z"]),
+    ]);
+    let btree_set = BTreeSet::from([300, 1, 70_000]);
+    let hash_set: HashSet<&str> = (0..100).map(|i| ["tag1", "tag2", "tag3"][i % 3]).collect();
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(btree_map_definition).unwrap();
+        table.insert(0, &btree_map).unwrap();
+        table.insert(1, &BTreeMap::new()).unwrap();
+        let mut table = write_txn.open_table(hash_map_definition).unwrap();
+        table.insert(0, &hash_map).unwrap();
+        let mut table = write_txn.open_table(btree_set_definition).unwrap();
+        table.insert(0, &btree_set).unwrap();
+        let mut table = write_txn.open_table(hash_set_definition).unwrap();
+        table.insert(0, &hash_set).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(btree_map_definition).unwrap();
+    assert_eq!(btree_map, table.get(0).unwrap().unwrap().value());
+    assert!(table.get(1).unwrap().unwrap().value().is_empty());
+    let table = read_txn.open_table(hash_map_definition).unwrap();
+    assert_eq!(hash_map, table.get(0).unwrap().unwrap().value());
+    let table = read_txn.open_table(btree_set_definition).unwrap();
+    assert_eq!(btree_set, table.get(0).unwrap().unwrap().value());
+    let table = read_txn.open_table(hash_set_definition).unwrap();
+    let guard = table.get(0).unwrap().unwrap();
+    assert_eq!(hash_set, guard.value());
+
+    // The encoding is deterministic, regardless of the iteration order of hash collections
+    let bytes = <HashSet<&str>>::as_bytes(&hash_set);
+    assert_eq!(
+        <HashSet<&str>>::as_bytes(&<HashSet<&str>>::from_bytes(&bytes)),
+        bytes
+    );
+    let sorted: BTreeSet<&str> = hash_set.iter().copied().collect();
+    assert_eq!(<BTreeSet<&str>>::as_bytes(&sorted), bytes);
+}
+
 #[test]
 fn vec_long_string_element() {
     // Vec elements with serialized length >= 254 bytes use the multi-byte varint path
@@ -2781,6 +3063,35 @@ fn range_arc() {
     assert!(iter.next().is_none());
 }
 
+#[test]
+fn range_without_cache_fill() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let options = ReadOptions::new().fill_cache(false);
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        for i in 0..1000 {
+            table.insert(i, i).unwrap();
+        }
+        // Uncommitted pages are visible
+        let values: Vec<u64> = table
+            .range_with(10..20, options)
+            .unwrap()
+            .map(|x| x.unwrap().1.value())
+            .collect();
+        assert_eq!(values, (10..20).collect::<Vec<u64>>());
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    let mut iter = table.range_with::<u64>(.., options).unwrap();
+    assert_eq!(iter.next_back().unwrap().unwrap().0.value(), 999);
+    assert_eq!(iter.count(), 999);
+}
+
 #[test]
 fn range_clone() {
     let tmpfile = create_tempfile();
@@ -2,7 +2,7 @@ use rand::RngExt;
 use rand::prelude::SliceRandom;
 use redb::backends::FileBackend;
 use redb::{
-    AccessGuard, Builder, CompactionError, Database, Durability, Key, MultimapRange,
+    AccessGuard, Builder, CachePolicy, CompactionError, Database, Durability, Key, MultimapRange,
     MultimapTableDefinition, MultimapValue, Range, ReadableDatabase, ReadableTable,
     ReadableTableMetadata, SetDurabilityError, StorageBackend, TableDefinition, TableStats,
     TransactionError, Value, WriteTransaction,
@@ -1110,6 +1110,854 @@ fn dynamic_growth() {
     assert!(file_size > initial_file_size);
 }
 
+#[test]
+fn configurable_page_size() {
+    let table_definition: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+    for page_size in [512, 1024, 16 * 1024, 64 * 1024] {
+        let tmpfile = create_tempfile();
+        let value = vec![0xAB; 3 * page_size / 2];
+
+        let db = Database::builder()
+            .set_page_size(page_size)
+            .create(tmpfile.path())
+            .unwrap();
+        let txn = db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(table_definition).unwrap();
+            for i in 0..100 {
+                table.insert(&i, value.as_slice()).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+        drop(db);
+
+        // The page size is read from the file, regardless of the configured page size
+        let db = Database::builder()
+            .set_page_size(if page_size == 512 { 1024 } else { 512 })
+            .open(tmpfile.path())
+            .unwrap();
+        let txn = db.begin_read().unwrap();
+        let table = txn.open_table(table_definition).unwrap();
+        assert_eq!(table.len().unwrap(), 100);
+        drop(table);
+        drop(txn);
+        drop(db);
+
+        let db = Database::open(tmpfile.path()).unwrap();
+        let txn = db.begin_read().unwrap();
+        let table = txn.open_table(table_definition).unwrap();
+        assert_eq!(table.len().unwrap(), 100);
+        for i in 0..100 {
+            assert_eq!(table.get(&i).unwrap().unwrap().value(), value.as_slice());
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "page size must be a power of two")]
+fn page_size_too_large() {
+    Database::builder().set_page_size(128 * 1024);
+}
+
+#[test]
+fn small_regions() {
+    let tmpfile = create_tempfile();
+    let table_definition: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+    let region_size = 64 * 1024;
+    let value = vec![0u8; 1024];
+
+    let db = Database::builder()
+        .set_region_size(region_size)
+        .create(tmpfile.path())
+        .unwrap();
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(table_definition).unwrap();
+        for i in 0..1024 {
+            table.insert(&i, value.as_slice()).unwrap();
+        }
+        // A pair can't span regions
+        let too_large = vec![0u8; 2 * region_size as usize];
+        assert!(matches!(
+            table.insert(&0, too_large.as_slice()),
+            Err(StorageError::ValueTooLarge(_))
+        ));
+    }
+    txn.commit().unwrap();
+    drop(db);
+
+    let mut db = Database::open(tmpfile.path()).unwrap();
+    assert!(db.check_integrity().unwrap());
+    let txn = db.begin_read().unwrap();
+    let table = txn.open_table(table_definition).unwrap();
+    assert_eq!(table.len().unwrap(), 1024);
+}
+
+#[test]
+fn smallest_region_size() {
+    let tmpfile = create_tempfile();
+    let table_definition: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+
+    // Rounded up to 4KiB, the smallest region which can hold the allocator state
+    let db = Database::builder()
+        .set_page_size(512)
+        .set_region_size(512)
+        .create(tmpfile.path())
+        .unwrap();
+    let mut txn = db.begin_write().unwrap();
+    // Quick repair saves the allocator state in every commit
+    txn.set_quick_repair(true);
+    {
+        let mut table = txn.open_table(table_definition).unwrap();
+        for i in 0..100 {
+            table.insert(&i, vec![0u8; 3000].as_slice()).unwrap();
+        }
+        assert!(matches!(
+            table.insert(&0, vec![0u8; 4096].as_slice()),
+            Err(StorageError::ValueTooLarge(_))
+        ));
+    }
+    txn.commit().unwrap();
+    drop(db);
+
+    let mut db = Database::open(tmpfile.path()).unwrap();
+    assert!(db.check_integrity().unwrap());
+    let txn = db.begin_read().unwrap();
+    let table = txn.open_table(table_definition).unwrap();
+    assert_eq!(table.len().unwrap(), 100);
+}
+
+#[test]
+fn preallocate() {
+    let tmpfile = create_tempfile();
+    let preallocated = 16 * 1024 * 1024;
+
+    let db = Database::builder()
+        .set_preallocate(preallocated)
+        .create(tmpfile.path())
+        .unwrap();
+    assert!(tmpfile.as_file().metadata().unwrap().len() >= preallocated);
+
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(U64_TABLE).unwrap();
+        table.insert(0, 0).unwrap();
+    }
+    txn.commit().unwrap();
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(U64_TABLE).unwrap();
+        table.remove(0).unwrap();
+    }
+    txn.commit().unwrap();
+    assert!(tmpfile.as_file().metadata().unwrap().len() >= preallocated);
+    drop(db);
+    assert!(tmpfile.as_file().metadata().unwrap().len() >= preallocated);
+}
+
+#[test]
+fn reserve() {
+    let tmpfile = create_tempfile();
+    let table_definition: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+    let value = vec![0u8; 1024];
+
+    let db = Database::create(tmpfile.path()).unwrap();
+    let initial_size = tmpfile.as_file().metadata().unwrap().len();
+    db.reserve(8 * 1024 * 1024).unwrap();
+    let reserved_size = tmpfile.as_file().metadata().unwrap().len();
+    assert!(reserved_size > initial_size && reserved_size >= 8 * 1024 * 1024);
+
+    // Filling the reserved space must not grow the file
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(table_definition).unwrap();
+        for i in 0..2048 {
+            table.insert(&i, value.as_slice()).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+    assert_eq!(tmpfile.as_file().metadata().unwrap().len(), reserved_size);
+
+    // Nor does freeing it shrink the file
+    let txn = db.begin_write().unwrap();
+    txn.delete_table(table_definition).unwrap();
+    txn.commit().unwrap();
+    let txn = db.begin_write().unwrap();
+    txn.commit().unwrap();
+    assert_eq!(tmpfile.as_file().metadata().unwrap().len(), reserved_size);
+}
+
+#[test]
+fn growth_step() {
+    let tmpfile = create_tempfile();
+    let table_definition: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+    let growth_step = 32 * 1024 * 1024;
+    let value = vec![0u8; 1024];
+
+    let db = Database::builder()
+        .set_growth_step(growth_step)
+        .create(tmpfile.path())
+        .unwrap();
+    let initial_size = tmpfile.as_file().metadata().unwrap().len();
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(table_definition).unwrap();
+        for i in 0..2048 {
+            table.insert(&i, value.as_slice()).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+    let grown_size = tmpfile.as_file().metadata().unwrap().len();
+    assert!(grown_size >= initial_size + growth_step);
+
+    // The free space left by the growth step is not released by later commits
+    for _ in 0..3 {
+        let txn = db.begin_write().unwrap();
+        txn.commit().unwrap();
+    }
+    assert_eq!(tmpfile.as_file().metadata().unwrap().len(), grown_size);
+}
+
+#[test]
+fn warm_and_pin_cache() {
+    let tmpfile = create_tempfile();
+    let table1: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+    let table2: TableDefinition<u64, &[u8]> = TableDefinition::new("y");
+    let multimap_table: MultimapTableDefinition<u64, u64> = MultimapTableDefinition::new("z");
+    let value = vec![0u8; 1024];
+
+    let db = Database::create(tmpfile.path()).unwrap();
+    let txn = db.begin_write().unwrap();
+    for definition in [table1, table2] {
+        let mut table = txn.open_table(definition).unwrap();
+        for i in 0..1000u64 {
+            table.insert(&i, value.as_slice()).unwrap();
+        }
+    }
+    {
+        let mut table = txn.open_multimap_table(multimap_table).unwrap();
+        for i in 0..1000u64 {
+            table.insert(&(i % 100), &i).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+    drop(db);
+
+    let mut db = Database::builder()
+        .set_cache_size(512 * 1024)
+        .open(tmpfile.path())
+        .unwrap();
+    db.pin_table_branches(table1);
+    db.warm_cache(&[&table1, &table2], &[&multimap_table], 256 * 1024, true)
+        .unwrap();
+    db.warm_cache(&[&table1], &[], 0, false).unwrap();
+    let missing: TableDefinition<u64, u64> = TableDefinition::new("missing");
+    assert!(matches!(
+        db.warm_cache(&[&missing], &[], 1024, false).unwrap_err(),
+        redb::Error::TableDoesNotExist(_)
+    ));
+    let missing: MultimapTableDefinition<u64, u64> = MultimapTableDefinition::new("missing");
+    assert!(matches!(
+        db.warm_cache(&[], &[&missing], 1024, false).unwrap_err(),
+        redb::Error::TableDoesNotExist(_)
+    ));
+
+    // Pinned pages are replaced as the table is modified
+    for round in 0..3u64 {
+        let txn = db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(table1).unwrap();
+            for i in (round..1000u64).step_by(3) {
+                table.remove(&i).unwrap();
+            }
+            for i in 1000..1100u64 {
+                table.insert(&(i + round * 100), value.as_slice()).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+
+        let txn = db.begin_read().unwrap();
+        let table = txn.open_table(table1).unwrap();
+        assert_eq!(
+            table.range::<u64>(..).unwrap().count(),
+            table.len().unwrap() as usize
+        );
+        let table = txn.open_table(table2).unwrap();
+        assert_eq!(table.range::<u64>(..).unwrap().count(), 1000);
+    }
+
+    db.unpin_table_branches(table1);
+    let txn = db.begin_read().unwrap();
+    let table = txn.open_table(table1).unwrap();
+    assert_eq!(table.len().unwrap(), 300);
+    assert_eq!(table.range::<u64>(..).unwrap().count(), 300);
+    drop(table);
+    drop(txn);
+    assert!(db.check_integrity().unwrap());
+}
+
+#[test]
+fn live_read_transactions() {
+    let tmpfile = create_tempfile();
+    let warned = Arc::new(Mutex::new(vec![]));
+    let warned2 = warned.clone();
+    let db = Database::builder()
+        .set_read_transaction_backtraces(true)
+        .set_read_transaction_warning(Duration::from_millis(10), move |info| {
+            warned2.lock().unwrap().push(info.transaction_id());
+        })
+        .create(tmpfile.path())
+        .unwrap();
+    assert!(db.live_read_transactions().is_empty());
+
+    let read1 = db.begin_read().unwrap();
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(U64_TABLE).unwrap();
+        table.insert(0, 0).unwrap();
+    }
+    txn.commit().unwrap();
+    let read2 = db.begin_read().unwrap();
+    // Savepoints are not reported
+    let txn = db.begin_write().unwrap();
+    let _savepoint = txn.ephemeral_savepoint().unwrap();
+
+    let live = db.live_read_transactions();
+    assert_eq!(live.len(), 2);
+    assert!(live[0].transaction_id() < live[1].transaction_id());
+    assert!(live[0].age() >= live[1].age());
+    assert!(live[0].backtrace().is_some());
+    assert!(warned.lock().unwrap().is_empty());
+    txn.abort().unwrap();
+
+    thread::sleep(Duration::from_millis(20));
+    drop(read2);
+    // The callback is invoked once, when the next transaction begins
+    let read3 = db.begin_read().unwrap();
+    drop(db.begin_read().unwrap());
+    assert_eq!(*warned.lock().unwrap(), vec![live[0].transaction_id()]);
+
+    drop(read1);
+    drop(read3);
+    assert!(db.live_read_transactions().is_empty());
+
+    // Backtraces are not captured by default
+    drop(db);
+    let db = Database::open(tmpfile.path()).unwrap();
+    let _read = db.begin_read().unwrap();
+    assert!(db.live_read_transactions()[0].backtrace().is_none());
+}
+
+#[test]
+fn read_transaction_expiry() {
+    let tmpfile = create_tempfile();
+    let db = Database::builder()
+        .set_max_read_transaction_age(Duration::from_millis(10))
+        .create(tmpfile.path())
+        .unwrap();
+    let value = vec![1u8; 1024];
+
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(SLICE_TABLE).unwrap();
+        for i in 0..100u64 {
+            table
+                .insert(i.to_le_bytes().as_slice(), value.as_slice())
+                .unwrap();
+        }
+    }
+    txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(SLICE_TABLE).unwrap();
+    let mut iter = table.range::<&[u8]>(..).unwrap();
+    let (_, first) = iter.next().unwrap().unwrap();
+    thread::sleep(Duration::from_millis(20));
+
+    // Beginning a write expires the read transaction, so its pages can be reused
+    let txn = db.begin_write().unwrap();
+    assert!(db.live_read_transactions().is_empty());
+    {
+        let mut table = txn.open_table(SLICE_TABLE).unwrap();
+        table.retain(|_, _| false).unwrap();
+    }
+    txn.commit().unwrap();
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(SLICE_TABLE).unwrap();
+        for i in 0..100u64 {
+            table
+                .insert(i.to_le_bytes().as_slice(), [2u8; 1024].as_slice())
+                .unwrap();
+        }
+    }
+    txn.commit().unwrap();
+
+    // Data which was already read remains valid
+    assert_eq!(first.value(), value.as_slice());
+    assert!(matches!(
+        table.get(50u64.to_le_bytes().as_slice()),
+        Err(StorageError::SnapshotExpired)
+    ));
+    assert!(matches!(
+        iter.find(|x| x.is_err()),
+        Some(Err(StorageError::SnapshotExpired))
+    ));
+    assert!(matches!(
+        read_txn.open_table(SLICE_TABLE),
+        Err(TableError::Storage(StorageError::SnapshotExpired))
+    ));
+    drop(iter);
+    drop(table);
+    drop(read_txn);
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(SLICE_TABLE).unwrap();
+    assert_eq!(
+        table
+            .get(50u64.to_le_bytes().as_slice())
+            .unwrap()
+            .unwrap()
+            .value(),
+        [2u8; 1024].as_slice()
+    );
+    drop(table);
+    drop(read_txn);
+    for round in 0..4u8 {
+        let txn = db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(SLICE_TABLE).unwrap();
+            for i in 0..100u64 {
+                table
+                    .insert(i.to_le_bytes().as_slice(), [round; 1024].as_slice())
+                    .unwrap();
+            }
+        }
+        txn.commit().unwrap();
+    }
+    assert_eq!(first.value(), value.as_slice());
+}
+
+#[test]
+fn dirty_page_budget() {
+    let tmpfile = create_tempfile();
+    let budget = 64 * 1024;
+    let db = Database::builder()
+        .set_dirty_page_budget(budget)
+        .create(tmpfile.path())
+        .unwrap();
+    let value = vec![7u8; 1024];
+
+    let txn = db.begin_write().unwrap();
+    let initial_bytes_written = db.io_stats().bytes_written();
+    {
+        let mut table = txn.open_table(U64_TABLE).unwrap();
+        let mut slice_table = txn.open_table(SLICE_TABLE).unwrap();
+        for i in 0..1000u64 {
+            table.insert(i, i).unwrap();
+            slice_table
+                .insert(i.to_le_bytes().as_slice(), value.as_slice())
+                .unwrap();
+            assert!(txn.dirty_bytes() <= budget);
+        }
+    }
+    // Pages beyond the budget were written to the file before the commit
+    assert!(db.io_stats().bytes_written() - initial_bytes_written > 512 * 1024);
+    assert!(txn.dirty_bytes() > 0);
+    txn.commit().unwrap();
+    assert_eq!(db.begin_write().unwrap().dirty_bytes(), 0);
+
+    drop(db);
+    let db = Database::open(tmpfile.path()).unwrap();
+    let txn = db.begin_read().unwrap();
+    let table = txn.open_table(U64_TABLE).unwrap();
+    let slice_table = txn.open_table(SLICE_TABLE).unwrap();
+    for i in 0..1000u64 {
+        assert_eq!(table.get(i).unwrap().unwrap().value(), i);
+        assert_eq!(
+            slice_table
+                .get(i.to_le_bytes().as_slice())
+                .unwrap()
+                .unwrap()
+                .value(),
+            value.as_slice()
+        );
+    }
+}
+
+#[test]
+fn dirty_page_budget_capped_by_cache_size() {
+    let tmpfile = create_tempfile();
+    let cache_size = 256 * 1024;
+    let db = Database::builder()
+        .set_cache_size(cache_size)
+        .set_dirty_page_budget(16 * 1024 * 1024)
+        .create(tmpfile.path())
+        .unwrap();
+    let value = vec![7u8; 1024];
+
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(SLICE_TABLE).unwrap();
+        for i in 0..1000u64 {
+            table
+                .insert(i.to_le_bytes().as_slice(), value.as_slice())
+                .unwrap();
+            assert!(txn.dirty_bytes() <= cache_size / 2);
+        }
+    }
+    txn.commit().unwrap();
+}
+
+#[test]
+fn io_stats() {
+    let tmpfile = create_tempfile();
+    let table1: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+    let table2: TableDefinition<u64, &[u8]> = TableDefinition::new("y");
+    let value = vec![0u8; 1024];
+
+    let db = Database::builder()
+        .set_table_io_stats(true)
+        .create(tmpfile.path())
+        .unwrap();
+    let initial = db.io_stats();
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(table1).unwrap();
+        for i in 0..100u64 {
+            table.insert(&i, value.as_slice()).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(table1).unwrap();
+        for i in 0..50u64 {
+            table.remove(&i).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+
+    let stats = db.io_stats();
+    assert_eq!(stats.commits() - initial.commits(), 2);
+    assert!(stats.fsyncs() > initial.fsyncs());
+    assert!(stats.bytes_written() > initial.bytes_written());
+    assert_eq!(stats.commit_latency_us().count(), stats.commits());
+    assert_eq!(stats.commit_bytes_written().count(), stats.commits());
+    assert!(stats.commit_bytes_written().quantile(1.0).unwrap() > 0);
+    assert!(stats.table("y").is_none());
+    let table_stats = stats.table("x").unwrap();
+    assert!(table_stats.pages_allocated() > 0);
+    assert!(table_stats.pages_freed() > 0);
+    assert!(table_stats.bytes_written() >= 100 * 1024);
+    assert!(table_stats.page_reads() > 0);
+    assert_eq!(
+        table_stats.page_reads(),
+        table_stats.cache_hits() + table_stats.cache_misses()
+    );
+
+    let txn = db.begin_read().unwrap();
+    let table = txn.open_table(table1).unwrap();
+    let reads = db.io_stats().table("x").unwrap().page_reads();
+    assert!(table.get(&0).unwrap().is_none());
+    assert!(db.io_stats().table("x").unwrap().page_reads() > reads);
+    assert!(txn.open_table(table2).is_err());
+    drop(table);
+    drop(txn);
+    drop(db);
+
+    // Per-table statistics are disabled by default
+    let db = Database::open(tmpfile.path()).unwrap();
+    let txn = db.begin_read().unwrap();
+    txn.open_table(table1).unwrap();
+    assert_eq!(db.io_stats().tables().count(), 0);
+}
+
+#[test]
+fn cache_policies() {
+    let table_definition: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+    let value = vec![0u8; 1024];
+
+    for policy in [
+        CachePolicy::Lru,
+        CachePolicy::TwoQueue,
+        CachePolicy::Adaptive,
+    ] {
+        let tmpfile = create_tempfile();
+        // Small enough that reads evict pages from the cache
+        let db = Database::builder()
+            .set_cache_size(256 * 1024)
+            .set_cache_policy(policy)
+            .create(tmpfile.path())
+            .unwrap();
+        let txn = db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(table_definition).unwrap();
+            for i in 0..2000u64 {
+                table.insert(&i, value.as_slice()).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+
+        let txn = db.begin_read().unwrap();
+        let table = txn.open_table(table_definition).unwrap();
+        for _ in 0..2 {
+            // A working set of keys which are read repeatedly, interleaved with a full scan
+            for i in 0..20u64 {
+                assert_eq!(table.get(&i).unwrap().unwrap().value(), value.as_slice());
+            }
+            assert_eq!(table.range::<u64>(..).unwrap().count(), 2000);
+        }
+        drop(table);
+        drop(txn);
+
+        let txn = db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(table_definition).unwrap();
+            for i in (0..2000u64).step_by(2) {
+                table.remove(&i).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+        drop(db);
+
+        let mut db = Database::builder()
+            .set_cache_size(256 * 1024)
+            .set_cache_policy(policy)
+            .open(tmpfile.path())
+            .unwrap();
+        assert!(db.check_integrity().unwrap());
+        let txn = db.begin_read().unwrap();
+        let table = txn.open_table(table_definition).unwrap();
+        assert_eq!(table.len().unwrap(), 1000);
+    }
+}
+
+#[test]
+fn max_file_size() {
+    let tmpfile = create_tempfile();
+    let table_definition: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+    let limit = 4 * 1024 * 1024;
+    let value = vec![0u8; 1024];
+
+    let db = Database::builder()
+        .set_max_file_size(limit)
+        .create(tmpfile.path())
+        .unwrap();
+
+    // Fill the database, one transaction at a time, until the limit is reached
+    let mut committed = 0;
+    let mut txn = db.begin_write().unwrap();
+    loop {
+        let mut table = txn.open_table(table_definition).unwrap();
+        let mut inserted = 0;
+        let mut error = None;
+        for i in committed..(committed + 100) {
+            if let Err(err) = table.insert(&i, value.as_slice()) {
+                error = Some(err);
+                break;
+            }
+            inserted += 1;
+        }
+        if let Some(err) = error {
+            assert!(matches!(err, StorageError::FileSizeLimitExceeded(x) if x == limit));
+            // The failed insert had no effect, and the transaction can still be used to remove data
+            assert_eq!(table.len().unwrap(), committed + inserted);
+            assert!(table.get(&(committed + inserted)).unwrap().is_none());
+            assert!(table.remove(&0).unwrap().is_some());
+            drop(table);
+            txn.commit().unwrap();
+            committed += inserted;
+            break;
+        }
+        drop(table);
+        txn.commit().unwrap();
+        committed += 100;
+        assert!(tmpfile.as_file().metadata().unwrap().len() <= limit);
+        txn = db.begin_write().unwrap();
+    }
+    assert!(tmpfile.as_file().metadata().unwrap().len() <= limit);
+    assert!(committed > 1000);
+
+    // Removing data frees up space for new data, once the removal is committed
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(table_definition).unwrap();
+        for i in 0..(committed / 2) {
+            table.remove(&i).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+    let txn = db.begin_write().unwrap();
+    txn.commit().unwrap();
+
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(table_definition).unwrap();
+        for i in 0..(committed / 4) {
+            table.insert(&i, value.as_slice()).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+    assert!(tmpfile.as_file().metadata().unwrap().len() <= limit);
+
+    // Reserving space past the limit fails
+    assert!(matches!(
+        db.reserve(limit),
+        Err(redb::Error::FileSizeLimitExceeded(_))
+    ));
+
+    drop(db);
+    let mut db = Database::builder()
+        .set_max_file_size(limit)
+        .open(tmpfile.path())
+        .unwrap();
+    assert!(db.check_integrity().unwrap());
+    let txn = db.begin_read().unwrap();
+    let table = txn.open_table(table_definition).unwrap();
+    assert_eq!(
+        table.len().unwrap(),
+        committed - committed / 2 + committed / 4
+    );
+}
+
+#[test]
+fn max_file_size_all_operations() {
+    let tmpfile = create_tempfile();
+    let limit = 2 * 1024 * 1024;
+    let value = vec![0u8; 1024];
+    let multimap_definition: MultimapTableDefinition<u64, &[u8]> =
+        MultimapTableDefinition::new("multimap");
+
+    let db = Database::builder()
+        .set_max_file_size(limit)
+        .create(tmpfile.path())
+        .unwrap();
+    let file_len = || tmpfile.as_file().metadata().unwrap().len();
+
+    // Create tables, savepoints and multimap values until the limit is reached. Each step either
+    // commits, or fails with FileSizeLimitExceeded without growing the file past the limit
+    let is_limit =
+        |err: &redb::Error| matches!(err, redb::Error::FileSizeLimitExceeded(x) if *x == limit);
+    let mut tables = 0;
+    let mut savepoints = vec![];
+    let mut multimap_values = 0;
+    let mut removed_values = 0;
+    loop {
+        let mut txn = db.begin_write().unwrap();
+        txn.set_quick_repair(tables % 2 == 0);
+        let savepoint = if tables % 4 == 0 {
+            match txn.persistent_savepoint() {
+                Ok(id) => Some(id),
+                Err(err) => {
+                    let err: redb::Error = err.into();
+                    assert!(is_limit(&err), "{err}");
+                    txn.abort().unwrap();
+                    break;
+                }
+            }
+        } else {
+            None
+        };
+        let result = (|| -> Result<(), redb::Error> {
+            let name = format!("table{tables}");
+            let definition: TableDefinition<u64, &[u8]> = TableDefinition::new(&name);
+            let mut table = txn.open_table(definition)?;
+            for i in 0..20 {
+                table.insert(&i, value.as_slice())?;
+            }
+            drop(table);
+            let mut multimap = txn.open_multimap_table(multimap_definition)?;
+            for i in 0..5 {
+                multimap.insert(&(tables % 3), vec![i; 300 + tables as usize].as_slice())?;
+            }
+            Ok(())
+        })();
+        match result {
+            Ok(()) => {}
+            Err(err) => {
+                assert!(is_limit(&err), "{err}");
+                txn.abort().unwrap();
+                break;
+            }
+        }
+        match txn.commit() {
+            Ok(()) => {
+                if tables % 3 == 0 {
+                    removed_values += 5;
+                }
+                tables += 1;
+                multimap_values += 5;
+                savepoints.extend(savepoint);
+            }
+            Err(err) => {
+                let err: redb::Error = err.into();
+                assert!(is_limit(&err), "{err}");
+                break;
+            }
+        }
+        assert!(file_len() <= limit);
+    }
+    assert!(file_len() <= limit);
+    assert!(tables > 10);
+
+    // Deleting tables and savepoints, and removing data with retain() and extract_if(), stays
+    // within the limit
+    let txn = db.begin_write().unwrap();
+    for id in savepoints {
+        assert!(txn.delete_persistent_savepoint(id).unwrap());
+    }
+    for i in 0..(tables / 2) {
+        let name = format!("table{i}");
+        let definition: TableDefinition<u64, &[u8]> = TableDefinition::new(&name);
+        assert!(txn.delete_table(definition).unwrap());
+    }
+    txn.commit().unwrap();
+    assert!(file_len() <= limit);
+
+    let txn = db.begin_write().unwrap();
+    for i in (tables / 2)..tables {
+        let name = format!("table{i}");
+        let definition: TableDefinition<u64, &[u8]> = TableDefinition::new(&name);
+        let mut table = txn.open_table(definition).unwrap();
+        table.retain(|key, _| key % 2 == 0).unwrap();
+        assert_eq!(table.extract_if(|key, _| key % 4 == 0).unwrap().count(), 5);
+    }
+    txn.open_multimap_table(multimap_definition)
+        .unwrap()
+        .remove_all(&0)
+        .unwrap();
+    txn.commit().unwrap();
+    assert!(file_len() <= limit);
+
+    // The freed space can be used again
+    let txn = db.begin_write().unwrap();
+    txn.commit().unwrap();
+    let txn = db.begin_write().unwrap();
+    {
+        let definition: TableDefinition<u64, &[u8]> = TableDefinition::new("table0");
+        let mut table = txn.open_table(definition).unwrap();
+        for i in 0..100 {
+            table.insert(&i, value.as_slice()).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+    assert!(file_len() <= limit);
+
+    drop(db);
+    assert!(file_len() <= limit);
+    let mut db = Database::builder()
+        .set_max_file_size(limit)
+        .open(tmpfile.path())
+        .unwrap();
+    assert!(db.check_integrity().unwrap());
+    let txn = db.begin_read().unwrap();
+    let multimap = txn.open_multimap_table(multimap_definition).unwrap();
+    assert!(multimap.get(&0).unwrap().is_empty());
+    assert_eq!(multimap.len().unwrap(), multimap_values - removed_values);
+}
+
 #[test]
 fn multi_page_kv() {
     let tmpfile = create_tempfile();
@@ -3044,6 +3892,176 @@ fn compaction() {
     assert!(file_size2 < file_size);
 }
 
+#[test]
+fn compact_step_with_live_reader() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let definition: TableDefinition<u32, &[u8]> = TableDefinition::new("x");
+
+    let value = vec![7u8; 1024];
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(definition).unwrap();
+        for i in 0..2000 {
+            table.insert(&i, value.as_slice()).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(definition).unwrap();
+        for i in 0..1900 {
+            table.remove(&i).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+    let file_size = tmpfile.as_file().metadata().unwrap().len();
+
+    let read_txn = db.begin_read().unwrap();
+    assert!(db.compact_step(64).unwrap());
+    // Writes may be interleaved with compaction steps
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(definition).unwrap();
+        table.insert(&5000, value.as_slice()).unwrap();
+    }
+    txn.commit().unwrap();
+    assert!(db.compact_step(64).unwrap());
+
+    // The reader still sees its snapshot
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(table.len().unwrap(), 100);
+    for i in 1900..2000 {
+        assert_eq!(table.get(&i).unwrap().unwrap().value(), value.as_slice());
+    }
+    assert!(table.get(&5000).unwrap().is_none());
+    drop(table);
+    drop(read_txn);
+
+    while db.compact_step(64).unwrap() {}
+    let file_size2 = tmpfile.as_file().metadata().unwrap().len();
+    assert!(file_size2 < file_size);
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(table.len().unwrap(), 101);
+    for i in (1900..2000).chain([5000]) {
+        assert_eq!(table.get(&i).unwrap().unwrap().value(), value.as_slice());
+    }
+}
+
+#[test]
+fn compact_step_with_persistent_savepoint() {
+    let tmpfile = create_tempfile();
+    let mut db = Database::create(tmpfile.path()).unwrap();
+
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(U64_TABLE).unwrap();
+        for i in 0..10_000 {
+            table.insert(i, i).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+
+    let txn = db.begin_write().unwrap();
+    let savepoint_id = txn.persistent_savepoint().unwrap();
+    txn.commit().unwrap();
+
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(U64_TABLE).unwrap();
+        table.retain(|k, _| k % 10 == 0).unwrap();
+    }
+    txn.commit().unwrap();
+
+    while db.compact_step(16).unwrap() {}
+    assert!(db.check_integrity().unwrap());
+
+    let mut txn = db.begin_write().unwrap();
+    let savepoint = txn.get_persistent_savepoint(savepoint_id).unwrap();
+    txn.restore_savepoint(&savepoint).unwrap();
+    txn.delete_persistent_savepoint(savepoint_id).unwrap();
+    txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 10_000);
+    for i in 0..10_000 {
+        assert_eq!(table.get(i).unwrap().unwrap().value(), i);
+    }
+    drop(table);
+    drop(read_txn);
+    assert!(db.check_integrity().unwrap());
+}
+
+#[test]
+fn space_stats() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let definition: TableDefinition<u32, &[u8]> = TableDefinition::new("x");
+
+    let value = vec![0u8; 1024];
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(definition).unwrap();
+        for i in 0..1000 {
+            table.insert(&i, value.as_slice()).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(definition).unwrap();
+        table.retain(|k, _| k < 100).unwrap();
+    }
+    txn.commit().unwrap();
+
+    let txn = db.begin_write().unwrap();
+    let stats = txn.space_stats().unwrap();
+    // The deleted pages are still referenced by the read transaction
+    assert!(stats.pending_free_pages() > 0);
+    assert_eq!(
+        stats.file_len(),
+        tmpfile.as_file().metadata().unwrap().len()
+    );
+    assert_eq!(
+        stats.allocated_pages() + stats.free_pages(),
+        stats.regions().iter().map(|x| x.pages()).sum::<u64>()
+    );
+    for region in stats.regions() {
+        let free_pages: u64 = region
+            .free_blocks_by_order()
+            .iter()
+            .enumerate()
+            .map(|(order, blocks)| blocks << order)
+            .sum();
+        assert_eq!(free_pages, region.free_pages());
+        assert!(region.largest_free_run() <= region.free_pages());
+    }
+    txn.abort().unwrap();
+
+    drop(read_txn);
+    for _ in 0..2 {
+        let txn = db.begin_write().unwrap();
+        txn.commit().unwrap();
+    }
+    let txn = db.begin_write().unwrap();
+    let stats2 = txn.space_stats().unwrap();
+    assert_eq!(stats2.pending_free_pages(), 0);
+    assert!(stats2.free_pages() > stats.free_pages());
+    assert!(stats2.largest_free_run() > 0);
+    txn.abort().unwrap();
+
+    while db.compact_step(64).unwrap() {}
+    let txn = db.begin_write().unwrap();
+    let stats3 = txn.space_stats().unwrap();
+    assert!(stats3.file_len() < stats2.file_len());
+    assert!(stats3.shrinkable_bytes() < stats3.file_len());
+}
+
 #[test]
 fn compact_after_non_durable_commit() {
     let tmpfile = create_tempfile();